@@ -0,0 +1,197 @@
+// Headless benchmark of the bullet simulation, run with `cargo bench --bench bullets`.
+// Thousands of bullets are kept alive, the ones leaving the screen being replaced every frame,
+// and the time taken to update them and test them against asteroids is reported for both the
+// pooled storage used by the game and the Vec it used to rebuild every frame.
+// Then, enemy pattern emitters fill the screen with bullets aimed at a moving player.
+
+extern crate arcade_rs;
+extern crate rand;
+
+use arcade_rs::{phi, views};
+
+use ::phi::data::Rectangle;
+use ::phi::pool::Pool;
+use ::std::time::Instant;
+use ::views::bullets::{spawn_bullets, Bullet, BulletKind, Target, Team, WeaponDef, WeaponKind};
+use ::views::patterns::{self, PatternEmitter};
+
+const WEAPONS_PATH: &'static str = "assets/weapons.cfg";
+const AREA: (f64, f64) = (800.0, 600.0);
+const DT: f64 = 1.0 / 60.0;
+const FRAMES: usize = 600;
+const TARGETS: usize = 10;
+const BULLET_COUNTS: [usize; 4] = [1_000, 2_500, 5_000, 10_000];
+const EMITTER_COUNTS: [usize; 3] = [4, 8, 16];
+
+// Frame times, in milliseconds, sorted from fastest to slowest
+struct Timings(Vec<f64>);
+
+impl Timings {
+	fn report(&self, label: &str, live: usize) {
+		self.report_as(&format!("{:>6} bullets, {:<5}", live, label));
+	}
+
+	fn report_as(&self, label: &str) {
+		let times = &self.0;
+		let mean = times.iter().fold(0.0, |sum, t| sum + t) / times.len() as f64;
+		let variance = times.iter().fold(0.0, |sum, t| sum + (t - mean).powi(2)) / times.len() as f64;
+		let percentile = |p: f64| times[((times.len() - 1) as f64 * p) as usize];
+
+		println!("{} mean {:.3}ms, sd {:.3}ms, p50 {:.3}ms, p99 {:.3}ms, max {:.3}ms",
+			label, mean, variance.sqrt(), percentile(0.5), percentile(0.99), times[times.len() - 1]);
+	}
+}
+
+fn random_targets() -> Vec<Target> {
+	(0..TARGETS).map(|id| Target {
+		id: id,
+		rect: Rectangle {
+			x: AREA.0 * (0.5 + ::rand::random::<f64>() * 0.4),
+			y: (AREA.1 - 96.0) * ::rand::random::<f64>(),
+			w: 96.0,
+			h: 96.0,
+		},
+	}).collect()
+}
+
+// Fires a random weapon from a random spot of the player's movable region
+fn random_shot<F: FnMut(BulletKind)>(weapons: &[WeaponDef], fire: F) {
+	let weapon = &weapons[::rand::random::<usize>() % weapons.len()];
+	let x = AREA.0 * 0.7 * ::rand::random::<f64>();
+	let y = (AREA.1 - 40.0) * ::rand::random::<f64>();
+	spawn_bullets(weapon, (x, y + 6.0), (x, y + 29.0), 0.0, fire);
+}
+
+// Whether a bullet survives hitting the targets it overlaps, which never die here
+fn collide(bullet: &mut BulletKind, targets: &[Target]) -> bool {
+	for target in targets {
+		if target.rect.overlaps(bullet.rect()) && bullet.can_hit(target.id) &&
+			!bullet.register_hit(target.id) {
+			return false;
+		}
+	}
+	true
+}
+
+fn run_pool(weapons: &[WeaponDef], live: usize) -> Timings {
+	let targets = random_targets();
+	let mut bullets = Pool::with_capacity(live);
+	let mut times = Vec::with_capacity(FRAMES);
+
+	for _ in 0..FRAMES {
+		let start = Instant::now();
+
+		bullets.retain(|bullet: &mut BulletKind|
+			bullet.update(AREA, &targets, DT) && collide(bullet, &targets));
+
+		while bullets.len() < live {
+			random_shot(weapons, |bullet| { bullets.insert(bullet); });
+		}
+
+		times.push(elapsed_ms(start));
+	}
+
+	sorted(times)
+}
+
+fn run_vec(weapons: &[WeaponDef], live: usize) -> Timings {
+	let targets = random_targets();
+	let mut bullets: Vec<BulletKind> = Vec::with_capacity(live);
+	let mut times = Vec::with_capacity(FRAMES);
+
+	for _ in 0..FRAMES {
+		let start = Instant::now();
+
+		bullets = ::std::mem::replace(&mut bullets, vec![])
+			.into_iter()
+			.filter_map(|mut bullet| {
+				if bullet.update(AREA, &targets, DT) { Some(bullet) } else { None }
+			})
+			.collect();
+
+		bullets = ::std::mem::replace(&mut bullets, vec![])
+			.into_iter()
+			.filter_map(|mut bullet| {
+				if collide(&mut bullet, &targets) { Some(bullet) } else { None }
+			})
+			.collect();
+
+		while bullets.len() < live {
+			random_shot(weapons, |bullet| bullets.push(bullet));
+		}
+
+		times.push(elapsed_ms(start));
+	}
+
+	sorted(times)
+}
+
+// Emitters spread along the right edge fire every pattern at a player moving up and down
+fn run_patterns(emitter_count: usize) -> (Timings, usize) {
+	let all = [patterns::RING, patterns::SPIRAL, patterns::AIMED_FAN,
+		patterns::ROTATING_STREAM, patterns::CURTAIN];
+
+	let mut emitters: Vec<((f64, f64), PatternEmitter)> = (0..emitter_count).map(|i| {
+		let origin = (AREA.0 - 60.0, AREA.1 * (i as f64 + 0.5) / emitter_count as f64);
+		(origin, PatternEmitter::new(all[i % all.len()]))
+	}).collect();
+
+	let mut bullets: Pool<BulletKind> = Pool::with_capacity(1_024);
+	let mut times = Vec::with_capacity(FRAMES);
+	let mut peak = 0;
+
+	for frame in 0..FRAMES {
+		let start = Instant::now();
+		let player = (100.0, AREA.1 / 2.0 + 200.0 * (frame as f64 * DT).sin());
+		let player_rect = Rectangle::with_size(43.0, 39.0).center_at(player);
+
+		bullets.retain(|bullet: &mut BulletKind|
+			bullet.update(AREA, &[], DT) && !bullet.rect().overlaps(player_rect));
+
+		for &mut (origin, ref mut emitter) in &mut emitters {
+			emitter.update(origin, player, Team::Enemy, DT, |bullet| { bullets.insert(bullet); });
+		}
+
+		times.push(elapsed_ms(start));
+		peak = ::std::cmp::max(peak, bullets.len());
+	}
+
+	(sorted(times), peak)
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+	let elapsed = start.elapsed();
+	elapsed.as_secs() as f64 * 1_000.0 + elapsed.subsec_nanos() as f64 / 1_000_000.0
+}
+
+fn sorted(mut times: Vec<f64>) -> Timings {
+	times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+	Timings(times)
+}
+
+fn main() {
+	// Beams do not spawn bullets, leave them out
+	let weapons: Vec<WeaponDef> = WeaponDef::load_all(WEAPONS_PATH).unwrap()
+		.into_iter()
+		.filter(|weapon| match weapon.kind {
+			WeaponKind::Bullets(_) => true,
+			WeaponKind::Beam { .. } => false,
+		})
+		.collect();
+
+	if weapons.is_empty() {
+		panic!("No bullet weapon in {}", WEAPONS_PATH);
+	}
+
+	println!("{} frames of {:.1}ms against {} asteroids", FRAMES, DT * 1_000.0, TARGETS);
+
+	for &live in &BULLET_COUNTS {
+		run_pool(&weapons, live).report("pool", live);
+		run_vec(&weapons, live).report("vec", live);
+	}
+
+	for &count in &EMITTER_COUNTS {
+		let (timings, peak) = run_patterns(count);
+		timings.report_as(&format!("{:>3} emitters, up to {:>5} bullets,", count, peak));
+	}
+}
@@ -0,0 +1,9 @@
+// The game itself, shared by the binary and the benchmarks
+extern crate sdl2;
+extern crate sdl2_image;
+extern crate sdl2_ttf;
+extern crate sdl2_mixer;
+extern crate rand;
+
+pub mod phi;
+pub mod views;
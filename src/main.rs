@@ -1,13 +1,43 @@
-extern crate sdl2;
-extern crate sdl2_image;
-extern crate sdl2_ttf;
+extern crate arcade_rs;
 extern crate rand;
 
-mod phi;
-mod views;
+use arcade_rs::{phi, views};
+
+mod nettest;
 
 fn main() {
-    ::phi::spawn("ArcadeRS Shooter", |phi| {
-        Box::new(::views::main_menu::MainMenuView::new(phi))
+    // Play two instances against each other over loopback, without a window
+    if ::std::env::args().any(|arg| arg == "--net-test") {
+        ::nettest::run();
+        return;
+    }
+
+    // Play over the network, e.g. `--host 7777` on one machine and `--join 10.0.0.2:7777`
+    // on the other
+    let args: Vec<String> = ::std::env::args().collect();
+    let lockstep = match (args.get(1).map(|arg| &arg[..]), args.get(2)) {
+        (Some("--host"), Some(port)) => match port.parse() {
+            Ok(port) => Some(::phi::net::Lockstep::host(port)),
+            Err(_) => {
+                println!("Invalid port: {}", port);
+                return;
+            },
+        },
+        (Some("--join"), Some(address)) => Some(::phi::net::Lockstep::join(address)),
+        _ => None,
+    };
+
+    let lockstep = match lockstep {
+        Some(Ok(lockstep)) => Some(lockstep),
+        Some(Err(err)) => {
+            println!("Could not start the network game: {}", err);
+            return;
+        },
+        None => None,
+    };
+
+    ::phi::spawn("ArcadeRS Shooter", move |phi| match lockstep {
+        Some(lockstep) => Box::new(::views::netplay::NetplayView::new(phi, lockstep)),
+        None => Box::new(::views::main_menu::MainMenuView::new(phi)),
     });
 }
\ No newline at end of file
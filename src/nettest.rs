@@ -0,0 +1,168 @@
+// Headless test of the lockstep netplay, run with `cargo run --release -- --net-test`.
+// Two peers play the networked game over the loopback interface, without a window: both fly
+// a ship with random inputs only they know, and compare checksums of their states.
+// SDL only allows one context per process, so the peers take turns on the main thread, each
+// with a random number generator of its own.
+// With `--desync`, the guest presses a key for the host's player once, which both should notice.
+
+use ::phi::{Phi, ViewAction};
+use ::phi::net::{Lockstep, Status};
+use ::rand::XorShiftRng;
+use ::std::thread;
+use ::std::time::{Duration, Instant};
+use ::views::controls::Controls;
+use ::views::game::GameView;
+
+// Seconds of game simulated by every frame, as in a network game
+const STEP: f64 = 0.01;
+const FRAMES: u32 = 3_000;
+// Chance, every turn, of a peer's player switching to other keys
+const INPUT_CHANGE: f64 = 0.05;
+// Frame at which the guest cheats with `--desync`
+const DESYNC_FRAME: u32 = 1_000;
+const CONNECT_TIMEOUT: u64 = 5;
+// Time peers keep sending after their last frame, so that the other one gets everything
+const LINGER_MS: u64 = 500;
+
+// One side of the game, along with its own copy of it
+struct Peer {
+	lockstep: Lockstep,
+	// Starts once connected, the host having picked the seed
+	game: Option<GameView>,
+	// Swapped in while the peer simulates, see `::phi::rng::swap`
+	rng: XorShiftRng,
+	input: u16,
+	checksum: u64,
+	cheat: bool,
+	// Whether the game ended, e.g. with both players out of lives
+	over: bool,
+}
+
+impl Peer {
+	fn new(lockstep: Lockstep, cheat: bool) -> Peer {
+		Peer {
+			lockstep: lockstep,
+			game: None,
+			rng: XorShiftRng::new_unseeded(),
+			input: 0,
+			checksum: 0,
+			cheat: cheat,
+			over: false,
+		}
+	}
+
+	fn playing(&self) -> bool {
+		self.lockstep.status() == Status::Running && self.lockstep.frame() < FRAMES && !self.over
+	}
+
+	// Simulates whatever frames the peer has both players' inputs for
+	fn play(&mut self, phi: &mut Phi) {
+		self.lockstep.poll();
+		if !self.playing() {
+			return;
+		}
+
+		::phi::rng::swap(&mut self.rng);
+
+		if self.game.is_none() {
+			self.game = Some(GameView::networked(phi, self.lockstep.seed()));
+		}
+
+		// The peers' players do not press the same keys, which only reach the other peer
+		// through the inputs it is sent
+		if ::rand::random::<f64>() < INPUT_CHANGE {
+			self.input = random_input();
+		}
+		self.lockstep.push_input(self.input);
+
+		let game = self.game.as_mut().unwrap();
+		while self.lockstep.frame() < FRAMES {
+			let mut inputs = match self.lockstep.next_inputs() {
+				Some(inputs) => inputs,
+				None => break,
+			};
+
+			if self.cheat && self.lockstep.frame() == DESYNC_FRAME {
+				inputs[0] ^= Controls { up: true, left: true, ..Controls::default() }.to_bits();
+			}
+
+			let controls = [Controls::from_bits(inputs[0]), Controls::from_bits(inputs[1])];
+			match game.update(phi, &controls, STEP) {
+				ViewAction::None => {},
+				_ => {
+					self.over = true;
+					break;
+				},
+			}
+
+			self.checksum = game.checksum();
+			self.lockstep.advance(self.checksum);
+		}
+
+		::phi::rng::swap(&mut self.rng);
+	}
+}
+
+// Keys held by a player who mashes whatever
+fn random_input() -> u16 {
+	Controls {
+		up: ::rand::random(),
+		down: ::rand::random(),
+		left: ::rand::random(),
+		right: ::rand::random(),
+		fire: ::rand::random(),
+		..Controls::default()
+	}.to_bits()
+}
+
+pub fn run() {
+	let cheat = ::std::env::args().any(|arg| arg == "--desync");
+
+	let host = Lockstep::host(0).unwrap();
+	let port = host.local_addr().unwrap().port();
+	let guest = Lockstep::join(&format!("127.0.0.1:{}", port)).unwrap();
+	let mut peers = [Peer::new(host, false), Peer::new(guest, cheat)];
+
+	let start = Instant::now();
+	::phi::headless(|phi| {
+		let connecting = Instant::now();
+		while peers.iter().any(|peer| peer.lockstep.status() == Status::Connecting)
+			&& connecting.elapsed() < Duration::from_secs(CONNECT_TIMEOUT) {
+			for peer in &mut peers {
+				peer.lockstep.poll();
+			}
+			thread::sleep(Duration::from_millis(1));
+		}
+
+		while peers.iter().any(Peer::playing) {
+			for peer in &mut peers {
+				peer.play(phi);
+			}
+			thread::sleep(Duration::from_millis(1));
+		}
+	});
+
+	// A peer that noticed a desync keeps sending its checksums too, for the other to notice
+	let lingering = Instant::now();
+	while lingering.elapsed() < Duration::from_millis(LINGER_MS)
+		&& peers.iter().all(|peer| peer.lockstep.status() != Status::Disconnected) {
+		for peer in &mut peers {
+			peer.lockstep.poll();
+		}
+		thread::sleep(Duration::from_millis(1));
+	}
+	let elapsed = start.elapsed();
+
+	for peer in &peers {
+		println!("player {}: {} frames, checksum {:016x}, {:?}{}",
+			peer.lockstep.player(), peer.lockstep.frame(), peer.checksum, peer.lockstep.status(),
+			if peer.over { ", game over" } else { "" });
+	}
+
+	let in_sync = peers.iter().all(|peer| peer.lockstep.status() == Status::Running)
+		&& peers[0].lockstep.frame() == peers[1].lockstep.frame()
+		&& peers[0].checksum == peers[1].checksum;
+
+	println!("{} after {}.{:03}s", if in_sync { "In sync" } else { "Out of sync" },
+		elapsed.as_secs(), elapsed.subsec_nanos() / 1_000_000);
+}
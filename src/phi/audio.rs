@@ -0,0 +1,322 @@
+use ::sdl2_mixer::{self, Channel, Chunk};
+use ::std::collections::HashMap;
+use ::std::path::Path;
+use ::std::rc::Rc;
+
+// Channels available to sound effects at the same time
+const SFX_CHANNELS: isize = 16;
+// Channels kept free for music, so that one track can fade into the other. The mixer only
+// plays on idle channels, so sounds never cut a track which is playing.
+const MUSIC_CHANNELS: isize = 2;
+// Time, in seconds, taken by one music track to replace the other
+const CROSSFADE_DURATION: f64 = 1.5;
+
+// Horizontal distance, in pixels, at which a sound is panned fully to one side
+const PAN_DISTANCE: f64 = 400.0;
+// Distance at which positional sounds reach their maximum attenuation
+const ATTENUATION_DISTANCE: f64 = 1_000.0;
+// Mixer distance (0 is right next to the listener, 255 is inaudible) of the farthest sounds
+const MAX_ATTENUATION: f64 = 180.0;
+
+// A looping music track and its current volume, between 0.0 and 1.0
+struct Track {
+	path: &'static str,
+	channel: Channel,
+	fade: f64,
+}
+
+// Plays cached sound effects and looping music.
+// If no audio device can be opened, e.g. when running headless, every call is a no-op.
+pub struct Audio {
+	enabled: bool,
+	cached_chunks: HashMap<&'static str, Rc<Chunk>>,
+
+	// The track being faded in and, during a crossfade, the one being faded out
+	music: Option<Track>,
+	fading_out: Option<Track>,
+
+	master_volume: f64,
+	music_volume: f64,
+	sfx_volume: f64,
+
+	// Position positional sounds are heard from, usually the player
+	listener: (f64, f64),
+}
+
+impl Audio {
+	pub fn new() -> Audio {
+		let enabled = match sdl2_mixer::open_audio(
+				sdl2_mixer::DEFAULT_FREQUENCY, sdl2_mixer::DEFAULT_FORMAT,
+				sdl2_mixer::DEFAULT_CHANNELS, 1_024) {
+			Ok(()) => {
+				sdl2_mixer::allocate_channels(SFX_CHANNELS + MUSIC_CHANNELS);
+				true
+			},
+			Err(err) => {
+				println!("Could not open the audio device, sound is disabled: {}", err);
+				false
+			},
+		};
+
+		Audio {
+			enabled: enabled,
+			cached_chunks: HashMap::new(),
+			music: None,
+			fading_out: None,
+			master_volume: 1.0,
+			music_volume: 1.0,
+			sfx_volume: 1.0,
+			listener: (0.0, 0.0),
+		}
+	}
+
+	pub fn set_listener(&mut self, position: (f64, f64)) {
+		self.listener = position;
+	}
+
+	// Volumes go from 0.0 (muted) to 1.0
+	pub fn set_volumes(&mut self, master: f64, music: f64, sfx: f64) {
+		self.master_volume = master.max(0.0).min(1.0);
+		self.music_volume = music.max(0.0).min(1.0);
+		self.sfx_volume = sfx.max(0.0).min(1.0);
+		self.update_music_volumes();
+	}
+
+	// Loads a sound the first time it is requested, then reuses it
+	fn chunk(&mut self, path: &'static str) -> Option<Rc<Chunk>> {
+		if let Some(chunk) = self.cached_chunks.get(path) {
+			return Some(chunk.clone());
+		}
+
+		match Chunk::from_file(Path::new(path)) {
+			Ok(chunk) => {
+				let chunk = Rc::new(chunk);
+				self.cached_chunks.insert(path, chunk.clone());
+				Some(chunk)
+			},
+			Err(err) => {
+				println!("Could not load {}: {}", path, err);
+				None
+			},
+		}
+	}
+
+	// Plays a sound effect on the first free channel, returning it so that it can be adjusted
+	pub fn play_sound(&mut self, path: &'static str) -> Option<Channel> {
+		if !self.enabled {
+			return None;
+		}
+
+		let volume = self.master_volume * self.sfx_volume;
+
+		self.chunk(path)
+			.and_then(|chunk| Channel::all().play(&chunk, 0).ok())
+			.map(|channel| {
+				channel.set_volume((volume * sdl2_mixer::MAX_VOLUME as f64) as isize);
+
+				// Channels are reused, remove any placement left by a positional sound
+				let _ = channel.set_panning(255, 255);
+				let _ = channel.set_distance(0);
+
+				channel
+			})
+	}
+
+	// Plays a sound effect panned and attenuated according to where it happens
+	// relative to the listener
+	pub fn play_sound_at(&mut self, path: &'static str, position: (f64, f64)) -> Option<Channel> {
+		let (left, right, distance) = self.placement(position);
+
+		self.play_sound(path).map(|channel| {
+			let _ = channel.set_panning(left, right);
+			let _ = channel.set_distance(distance);
+			channel
+		})
+	}
+
+	// Computes the left and right volumes, and the mixer distance, of a sound at `position`
+	fn placement(&self, position: (f64, f64)) -> (u8, u8, u8) {
+		let dx = position.0 - self.listener.0;
+		let dy = position.1 - self.listener.1;
+
+		// Constant-power panning keeps the loudness the same across the stereo field
+		let pan = (dx / PAN_DISTANCE).max(-1.0).min(1.0);
+		let angle = (pan + 1.0) * ::std::f64::consts::PI / 4.0;
+		let left = (angle.cos() * 255.0) as u8;
+		let right = (angle.sin() * 255.0) as u8;
+
+		let distance = (dx * dx + dy * dy).sqrt();
+		let attenuation = (distance / ATTENUATION_DISTANCE).min(1.0) * MAX_ATTENUATION;
+
+		(left, right, attenuation as u8)
+	}
+
+	// Loops a music track, crossfading from the current one. Does nothing if it is already playing.
+	pub fn play_music(&mut self, path: &'static str) {
+		if !self.enabled || self.music.as_ref().map_or(false, |track| track.path == path) {
+			return;
+		}
+
+		// Only two tracks can play at once, cut the one which is already fading out
+		if let Some(old) = self.fading_out.take() {
+			old.channel.halt();
+		}
+
+		let started = self.chunk(path)
+			.and_then(|chunk| Channel::all().play(&chunk, -1).ok());
+
+		self.fading_out = self.music.take();
+
+		if let Some(channel) = started {
+			channel.set_volume(0);
+			let _ = channel.set_panning(255, 255);
+			let _ = channel.set_distance(0);
+			self.music = Some(Track {
+				path: path,
+				channel: channel,
+				fade: 0.0,
+			});
+		}
+	}
+
+	// Fades the current music out
+	pub fn stop_music(&mut self) {
+		if let Some(old) = self.fading_out.take() {
+			old.channel.halt();
+		}
+
+		self.fading_out = self.music.take();
+	}
+
+	// Advances the crossfade, dt being expressed in seconds
+	pub fn update(&mut self, dt: f64) {
+		if !self.enabled {
+			return;
+		}
+
+		let step = dt / CROSSFADE_DURATION;
+
+		if let Some(ref mut track) = self.music {
+			track.fade = (track.fade + step).min(1.0);
+		}
+
+		let faded_out = match self.fading_out {
+			Some(ref mut track) => {
+				track.fade = (track.fade - step).max(0.0);
+				track.fade == 0.0
+			},
+			None => false,
+		};
+
+		if faded_out {
+			self.fading_out.take().unwrap().channel.halt();
+		}
+
+		self.update_music_volumes();
+	}
+
+	fn update_music_volumes(&self) {
+		let volume = self.master_volume * self.music_volume * sdl2_mixer::MAX_VOLUME as f64;
+
+		for track in self.music.iter().chain(self.fading_out.iter()) {
+			track.channel.set_volume((volume * track.fade) as isize);
+		}
+	}
+}
+
+impl Drop for Audio {
+	fn drop(&mut self) {
+		if self.enabled {
+			sdl2_mixer::close_audio();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::std::sync::{Mutex, Once};
+
+	// SDL can only be initialized once at a time, and the driver is picked from the environment
+	static SDL_LOCK_INIT: Once = Once::new();
+	static mut SDL_LOCK: *const Mutex<()> = 0 as *const Mutex<()>;
+
+	fn sdl_lock() -> &'static Mutex<()> {
+		unsafe {
+			SDL_LOCK_INIT.call_once(|| SDL_LOCK = Box::into_raw(Box::new(Mutex::new(()))));
+			&*SDL_LOCK
+		}
+	}
+
+	const SOUND: &'static str = "assets/sounds/shoot.wav";
+	const MENU_MUSIC: &'static str = "assets/sounds/music_menu.wav";
+	const GAME_MUSIC: &'static str = "assets/sounds/music_game.wav";
+
+	// Runs `test` with the audio driver named `driver`, as the game would on a headless machine
+	fn with_driver<F: FnOnce()>(driver: &str, test: F) {
+		let _lock = sdl_lock().lock().unwrap_or_else(|err| err.into_inner());
+		::std::env::set_var("SDL_AUDIODRIVER", driver);
+		let _sdl = ::sdl2::init().unwrap();
+		test();
+	}
+
+	#[test]
+	fn plays_sounds_on_the_dummy_driver() {
+		with_driver("dummy", || {
+			let mut audio = Audio::new();
+			assert!(audio.enabled);
+
+			assert!(audio.play_sound(SOUND).is_some());
+			// The chunk is loaded once, then reused
+			assert!(audio.play_sound_at(SOUND, (100.0, 0.0)).is_some());
+			assert_eq!(audio.cached_chunks.len(), 1);
+		});
+	}
+
+	#[test]
+	fn crossfades_music_on_the_dummy_driver() {
+		with_driver("dummy", || {
+			let mut audio = Audio::new();
+
+			audio.play_music(MENU_MUSIC);
+			assert_eq!(audio.music.as_ref().map(|track| (track.path, track.fade)), Some((MENU_MUSIC, 0.0)));
+			assert!(audio.fading_out.is_none());
+
+			audio.update(CROSSFADE_DURATION / 2.0);
+			assert_eq!(audio.music.as_ref().map(|track| track.fade), Some(0.5));
+
+			// Playing the same track again changes nothing
+			audio.play_music(MENU_MUSIC);
+			assert!(audio.fading_out.is_none());
+
+			audio.play_music(GAME_MUSIC);
+			let music = audio.music.as_ref().unwrap();
+			let fading_out = audio.fading_out.as_ref().unwrap();
+			assert_eq!((music.path, music.fade), (GAME_MUSIC, 0.0));
+			assert_eq!((fading_out.path, fading_out.fade), (MENU_MUSIC, 0.5));
+			assert!(music.channel != fading_out.channel);
+
+			audio.update(CROSSFADE_DURATION);
+			assert_eq!(audio.music.as_ref().map(|track| track.fade), Some(1.0));
+			assert!(audio.fading_out.is_none());
+
+			audio.stop_music();
+			assert!(audio.music.is_none());
+			assert_eq!(audio.fading_out.as_ref().map(|track| track.path), Some(GAME_MUSIC));
+		});
+	}
+
+	#[test]
+	fn stays_silent_without_a_device() {
+		with_driver("no-such-driver", || {
+			let mut audio = Audio::new();
+			assert!(!audio.enabled);
+
+			assert!(audio.play_sound(SOUND).is_none());
+			audio.play_music(MENU_MUSIC);
+			audio.update(CROSSFADE_DURATION);
+			assert!(audio.music.is_none());
+			assert!(audio.cached_chunks.is_empty());
+		});
+	}
+}
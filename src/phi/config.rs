@@ -0,0 +1,75 @@
+use ::std::fs::File;
+use ::std::io::{self, Read};
+use ::std::path::Path;
+use ::std::str::FromStr;
+
+// A block of `key = value` lines, introduced by a `[name]` header.
+// Lines found before the first header belong to a section with an empty name.
+pub struct Section {
+	pub name: String,
+	entries: Vec<(String, String)>,
+}
+
+impl Section {
+	// Returns the raw value of a key, the last one winning if it is repeated
+	pub fn get(&self, key: &str) -> Option<&str> {
+		self.entries.iter().rev()
+			.find(|&&(ref k, _)| k == key)
+			.map(|&(_, ref v)| &v[..])
+	}
+
+	// Returns every value of a key, in the order they appear
+	pub fn get_all<'a>(&'a self, key: &'a str) -> Box<Iterator<Item=&'a str> + 'a> {
+		Box::new(self.entries.iter()
+			.filter(move |&&(ref k, _)| k == key)
+			.map(|&(_, ref v)| &v[..]))
+	}
+
+	// Parses the value of a key, None if it is missing or invalid
+	pub fn parse<T: FromStr>(&self, key: &str) -> Option<T> {
+		self.get(key).and_then(|value| value.parse().ok())
+	}
+
+	pub fn parse_or<T: FromStr>(&self, key: &str, default: T) -> T {
+		self.parse(key).unwrap_or(default)
+	}
+}
+
+// Splits a config file into sections. Empty lines and lines starting with '#' are ignored.
+pub fn parse(contents: &str) -> Vec<Section> {
+	let mut sections = vec![Section { name: String::new(), entries: vec![] }];
+
+	for line in contents.lines() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		if line.starts_with('[') && line.ends_with(']') {
+			sections.push(Section {
+				name: line[1..line.len() - 1].trim().to_string(),
+				entries: vec![],
+			});
+			continue;
+		}
+
+		let mut parts = line.splitn(2, '=');
+		let key = parts.next().unwrap().trim();
+		if let Some(value) = parts.next() {
+			sections.last_mut().unwrap().entries.push((key.to_string(), value.trim().to_string()));
+		}
+	}
+
+	// Drop the unnamed section when the file starts with a header
+	if sections[0].entries.is_empty() && sections.len() > 1 {
+		sections.remove(0);
+	}
+
+	sections
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Vec<Section>> {
+	let mut contents = String::new();
+	try!(try!(File::open(path)).read_to_string(&mut contents));
+	Ok(parse(&contents))
+}
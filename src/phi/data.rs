@@ -82,20 +82,42 @@ impl Rectangle {
 
 		(x, y)
 	}
-}
 
-// Implementaiton of MaybeAlive - Used by Entities that may die that frame
-pub struct MaybeAlive<T> {
-	pub alive: bool,
-	pub value: T,
-}
+	// Casts a ray from origin along direction, returning how far along it the rectangle is
+	// first touched (in units of direction's length), or None if the ray misses it
+	pub fn raycast(&self, origin: (f64, f64), direction: (f64, f64)) -> Option<f64> {
+		// Intersect the ray with the slabs between both pairs of parallel edges
+		let slab = |origin: f64, dir: f64, min: f64, max: f64| -> Option<(f64, f64)> {
+			if dir == 0.0 {
+				if origin >= min && origin <= max {
+					Some((::std::f64::NEG_INFINITY, ::std::f64::INFINITY))
+				} else {
+					None
+				}
+			} else {
+				let t1 = (min - origin) / dir;
+				let t2 = (max - origin) / dir;
+				Some((t1.min(t2), t1.max(t2)))
+			}
+		};
 
-impl<T> MaybeAlive<T> {
-	pub fn as_option(self) -> Option<T> {
-		if self.alive {
-			Some(self.value)
-		} else {
+		let (x_near, x_far) = match slab(origin.0, direction.0, self.x, self.x + self.w) {
+			Some(range) => range,
+			None => return None,
+		};
+		let (y_near, y_far) = match slab(origin.1, direction.1, self.y, self.y + self.h) {
+			Some(range) => range,
+			None => return None,
+		};
+
+		let near = x_near.max(y_near);
+		let far = x_far.min(y_far);
+
+		if near > far || far < 0.0 {
 			None
+		} else {
+			// The origin may be inside of the rectangle
+			Some(near.max(0.0))
 		}
 	}
-}
\ No newline at end of file
+}
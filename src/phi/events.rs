@@ -10,7 +10,8 @@ macro_rules! struct_events {
 		use ::sdl2::EventPump;
 
 		pub struct ImmediateEvents {
-			resize: Option<(u32, u32)>,
+			// New size of the window in pixels, if it was resized this frame
+			pub resize: Option<(u32, u32)>,
 			$( pub $k_alias : Option<bool> , )*
 			$( pub $e_alias : bool ),*
 		}
@@ -3,7 +3,9 @@ use ::phi::Phi;
 use ::std::cell::RefCell;
 use ::std::path::Path;
 use ::std::rc::Rc;
-use ::sdl2::render::{Renderer, Texture};
+use ::sdl2::pixels::Color;
+use ::sdl2::rect::Point;
+use ::sdl2::render::{BlendMode, Renderer, Texture};
 use ::sdl2_image::LoadTexture;
 
 #[derive(Clone)]
@@ -52,6 +54,10 @@ impl Sprite {
 		(self.src.w, self.src.h)
 	}
 
+	// Identifies the underlying texture, shared by every region of the same image
+	pub fn texture_id(&self) -> usize {
+		&*self.tex as *const RefCell<Texture> as usize
+	}
 
 }
 
@@ -135,32 +141,560 @@ impl AnimatedSprite {
 	}
 }
 
+// Transformations applied to a sprite when it is copied to the screen
+#[derive(Clone, Copy, Debug)]
+pub struct DrawParams {
+	// Clockwise rotation in degrees
+	pub angle: f64,
+	// Point of rotation relative to the top-left of the destination, None for its center
+	pub pivot: Option<(f64, f64)>,
+	pub flip_h: bool,
+	pub flip_v: bool,
+	// Color modulation, (255, 255, 255) leaves the sprite untouched
+	pub tint: (u8, u8, u8),
+	pub alpha: u8,
+	pub blend: BlendMode,
+}
+
+impl DrawParams {
+	// Parameters which render the sprite exactly as `Renderable::render` would
+	pub fn new() -> DrawParams {
+		DrawParams {
+			angle: 0.0,
+			pivot: None,
+			flip_h: false,
+			flip_v: false,
+			tint: (255, 255, 255),
+			alpha: 255,
+			blend: BlendMode::Blend,
+		}
+	}
+
+	pub fn rotated(self, angle: f64) -> DrawParams {
+		DrawParams { angle: angle, ..self }
+	}
+
+	pub fn around(self, pivot: (f64, f64)) -> DrawParams {
+		DrawParams { pivot: Some(pivot), ..self }
+	}
+
+	pub fn flipped(self, horizontal: bool, vertical: bool) -> DrawParams {
+		DrawParams { flip_h: horizontal, flip_v: vertical, ..self }
+	}
+
+	pub fn tinted(self, color: Color) -> DrawParams {
+		let (r, g, b) = color.rgb();
+		DrawParams { tint: (r, g, b), ..self }
+	}
+
+	pub fn with_alpha(self, alpha: u8) -> DrawParams {
+		DrawParams { alpha: alpha, ..self }
+	}
+
+	// Sets the alpha from an opacity between 0.0 and 1.0
+	pub fn with_opacity(self, opacity: f64) -> DrawParams {
+		let opacity = opacity.max(0.0).min(1.0);
+		self.with_alpha((opacity * 255.0) as u8)
+	}
+
+	pub fn with_blend(self, blend: BlendMode) -> DrawParams {
+		DrawParams { blend: blend, ..self }
+	}
+}
+
 pub trait Renderable {
-	fn render(&self, renderer: &mut Renderer, dest: Rectangle);
+	// The sprite which would currently be drawn
+	fn frame(&self) -> &Sprite;
+
+	fn render(&self, renderer: &mut Renderer, dest: Rectangle) {
+		self.render_ex(renderer, dest, DrawParams::new());
+	}
+
+	fn render_ex(&self, renderer: &mut Renderer, dest: Rectangle, params: DrawParams);
 }
 
 impl Renderable for Sprite {
+	fn frame(&self) -> &Sprite {
+		self
+	}
+
 	fn render(&self, renderer: &mut Renderer, dest: Rectangle) {
 		renderer.copy(&mut self.tex.borrow_mut(), self.src.to_sdl(), dest.to_sdl())
-	}	
+	}
+
+	fn render_ex(&self, renderer: &mut Renderer, dest: Rectangle, params: DrawParams) {
+		let mut tex = self.tex.borrow_mut();
+
+		// Textures are shared between sprites, so remember the previous state to restore it
+		let old_color = tex.color_mod();
+		let old_alpha = tex.alpha_mod();
+		let old_blend = tex.blend_mode();
+
+		tex.set_color_mod(params.tint.0, params.tint.1, params.tint.2);
+		tex.set_alpha_mod(params.alpha);
+		tex.set_blend_mode(params.blend);
+
+		let center = params.pivot.map(|(x, y)| Point::new(x as i32, y as i32));
+		renderer.copy_ex(&tex, self.src.to_sdl(), dest.to_sdl(),
+			params.angle, center, (params.flip_h, params.flip_v));
+
+		tex.set_color_mod(old_color.0, old_color.1, old_color.2);
+		tex.set_alpha_mod(old_alpha);
+		tex.set_blend_mode(old_blend);
+	}
 }
 
 impl Renderable for AnimatedSprite {
-	fn render(&self, renderer: &mut Renderer, dest: Rectangle) {
+	fn frame(&self) -> &Sprite {
 		let current_frame =
 			(self.current_time / self.frame_delay) as usize % self.frames();
 
-		let sprite = &self.sprites[current_frame];
-		sprite.render(renderer, dest);
+		&self.sprites[current_frame]
+	}
+
+	fn render_ex(&self, renderer: &mut Renderer, dest: Rectangle, params: DrawParams) {
+		self.frame().render_ex(renderer, dest, params);
 	}
 }
 
 pub trait CopySprite<T> {
 	fn copy_sprite(&mut self, renderable: &T, dest: Rectangle);
+	fn copy_sprite_ex(&mut self, renderable: &T, dest: Rectangle, params: DrawParams);
 }
 
 impl<'window, T: Renderable> CopySprite<T> for Renderer<'window> {
 	fn copy_sprite(&mut self, renderable: &T, dest: Rectangle) {
 		renderable.render(self, dest);
 	}
+
+	fn copy_sprite_ex(&mut self, renderable: &T, dest: Rectangle, params: DrawParams) {
+		renderable.render_ex(self, dest, params);
+	}
+}
+
+
+// Layers are drawn back to front in the order they are declared
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+	Background,
+	Entities,
+	Effects,
+	Foreground,
+	Hud,
+}
+
+enum DrawKind {
+	Sprite { sprite: Sprite, params: Option<DrawParams> },
+	Fill { color: Color, blend: BlendMode },
+}
+
+struct DrawCommand {
+	layer: Layer,
+	z: i32,
+	dest: Rectangle,
+	kind: DrawKind,
+	// How strongly the camera affects this command, 0.0 pins it to the screen
+	parallax: f64,
+}
+
+impl DrawCommand {
+	// Rectangle fills do not use a texture and sort before sprites when batching
+	fn texture_id(&self) -> usize {
+		match self.kind {
+			DrawKind::Sprite { ref sprite, .. } => sprite.texture_id(),
+			DrawKind::Fill { .. } => 0,
+		}
+	}
+}
+
+// Counters for the last flushed frame, used for profiling
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderStats {
+	pub draw_calls: usize,
+	pub texture_switches: usize,
+}
+
+// Collects the draw commands of a frame and renders them sorted by layer and z
+pub struct RenderQueue {
+	commands: Vec<DrawCommand>,
+	batching: bool,
+	parallax: f64,
+	stats: RenderStats,
+}
+
+impl RenderQueue {
+	pub fn new() -> RenderQueue {
+		RenderQueue {
+			commands: Vec::new(),
+			batching: false,
+			parallax: 1.0,
+			stats: RenderStats::default(),
+		}
+	}
+
+	// When enabled, commands sharing a layer and z are grouped by texture
+	pub fn set_batching(&mut self, batching: bool) {
+		self.batching = batching;
+	}
+
+	// Sets the camera influence of the following submissions until the next flush.
+	// The Hud layer always ignores the camera.
+	pub fn set_parallax(&mut self, parallax: f64) {
+		self.parallax = parallax;
+	}
+
+	pub fn sprite<T: Renderable>(&mut self, layer: Layer, z: i32, renderable: &T, dest: Rectangle) {
+		self.push(layer, z, dest, DrawKind::Sprite {
+			sprite: renderable.frame().clone(),
+			params: None,
+		});
+	}
+
+	pub fn sprite_ex<T: Renderable>(&mut self, layer: Layer, z: i32, renderable: &T, dest: Rectangle, params: DrawParams) {
+		self.push(layer, z, dest, DrawKind::Sprite {
+			sprite: renderable.frame().clone(),
+			params: Some(params),
+		});
+	}
+
+	pub fn fill_rect(&mut self, layer: Layer, z: i32, rect: Rectangle, color: Color) {
+		self.fill_rect_ex(layer, z, rect, color, BlendMode::None);
+	}
+
+	pub fn fill_rect_ex(&mut self, layer: Layer, z: i32, rect: Rectangle, color: Color, blend: BlendMode) {
+		self.push(layer, z, rect, DrawKind::Fill { color: color, blend: blend });
+	}
+
+	fn push(&mut self, layer: Layer, z: i32, dest: Rectangle, kind: DrawKind) {
+		let parallax = if layer == Layer::Hud { 0.0 } else { self.parallax };
+
+		self.commands.push(DrawCommand {
+			layer: layer,
+			z: z,
+			dest: dest,
+			kind: kind,
+			parallax: parallax,
+		});
+	}
+
+	// Drops every submitted command without rendering it
+	pub fn clear(&mut self) {
+		self.commands.clear();
+		self.parallax = 1.0;
+	}
+
+	// Renders and empties the queue through the camera, returning the counters for this frame
+	// viewport: the logical size of the screen
+	pub fn flush(&mut self, renderer: &mut Renderer, camera: &Camera, viewport: (f64, f64)) -> RenderStats {
+		// The sort is stable, so commands with equal keys keep their submission order
+		if self.batching {
+			self.commands.sort_by(|a, b|
+				(a.layer, a.z, a.texture_id()).cmp(&(b.layer, b.z, b.texture_id())));
+		} else {
+			self.commands.sort_by(|a, b| (a.layer, a.z).cmp(&(b.layer, b.z)));
+		}
+
+		let mut stats = RenderStats::default();
+		let mut last_texture = None;
+
+		for command in self.commands.drain(..) {
+			let (dest, angle) = camera.transform(command.dest, command.parallax, viewport);
+
+			// Less than a pixel wide or high, there is nothing to draw, and SDL would take the
+			// missing destination for the whole screen
+			let rect = match dest.to_sdl() {
+				Some(rect) => rect,
+				None => continue,
+			};
+
+			let texture = command.texture_id();
+			if texture != 0 && last_texture != Some(texture) {
+				stats.texture_switches += 1;
+				last_texture = Some(texture);
+			}
+			stats.draw_calls += 1;
+
+			match command.kind {
+				DrawKind::Sprite { sprite, params: None } =>
+					if angle == 0.0 {
+						sprite.render(renderer, dest)
+					} else {
+						sprite.render_ex(renderer, dest, DrawParams::new().rotated(angle))
+					},
+				DrawKind::Sprite { sprite, params: Some(params) } =>
+					sprite.render_ex(renderer, dest, params.rotated(params.angle + angle)),
+				// Plain rectangles cannot be rotated, they only follow the camera's position and zoom
+				DrawKind::Fill { color, blend } => {
+					renderer.set_blend_mode(blend);
+					renderer.set_draw_color(color);
+					renderer.fill_rect(rect);
+				},
+			}
+		}
+
+		self.parallax = 1.0;
+		self.stats = stats;
+		stats
+	}
+
+	// Counters of the last call to `flush`
+	pub fn stats(&self) -> RenderStats {
+		self.stats
+	}
+}
+
+
+// Maps world coordinates to the screen. At its default position, world and screen
+// coordinates are the same.
+pub struct Camera {
+	// Offset of the visible region from the world's origin
+	pub position: (f64, f64),
+	pub zoom: f64,
+	// Clockwise rotation of the world around the center of the screen, in degrees
+	pub rotation: f64,
+
+	// Maximum displacement, in pixels and degrees, applied at full trauma
+	pub max_shake_offset: f64,
+	pub max_shake_angle: f64,
+	// Trauma lost every second
+	pub trauma_decay: f64,
+
+	trauma: f64,
+	time: f64,
+	shake_offset: (f64, f64),
+	shake_angle: f64,
+}
+
+impl Camera {
+	pub fn new() -> Camera {
+		Camera {
+			position: (0.0, 0.0),
+			zoom: 1.0,
+			rotation: 0.0,
+			max_shake_offset: 12.0,
+			max_shake_angle: 3.0,
+			trauma_decay: 1.2,
+			trauma: 0.0,
+			time: 0.0,
+			shake_offset: (0.0, 0.0),
+			shake_angle: 0.0,
+		}
+	}
+
+	// Adds to the screen shake, trauma is kept between 0.0 and 1.0
+	pub fn add_trauma(&mut self, amount: f64) {
+		self.trauma = (self.trauma + amount).max(0.0).min(1.0);
+	}
+
+	pub fn trauma(&self) -> f64 {
+		self.trauma
+	}
+
+	// Decays the trauma and picks the shake for this frame
+	pub fn update(&mut self, dt: f64) {
+		self.time += dt;
+		self.trauma = (self.trauma - self.trauma_decay * dt).max(0.0);
+
+		// Squaring the trauma makes small hits subtle and big hits violent
+		let shake = self.trauma * self.trauma;
+
+		// Sum of unrelated sines, smoother than picking random values every frame
+		let t = self.time;
+		let noise = |a: f64, b: f64| (f64::sin(t * a) + f64::sin(t * b + 1.7)) / 2.0;
+
+		self.shake_offset = (
+			self.max_shake_offset * shake * noise(47.0, 71.0),
+			self.max_shake_offset * shake * noise(53.0, 67.0));
+		self.shake_angle = self.max_shake_angle * shake * noise(41.0, 59.0);
+	}
+
+	// Maps a world point to the screen, the camera's effect being scaled by parallax
+	pub fn world_to_screen(&self, point: (f64, f64), parallax: f64, viewport: (f64, f64)) -> (f64, f64) {
+		let center = (viewport.0 / 2.0, viewport.1 / 2.0);
+		let zoom = 1.0 + (self.zoom - 1.0) * parallax;
+		let angle = (self.rotation + self.shake_angle) * parallax;
+
+		let rel_x = (point.0 - (self.position.0 + self.shake_offset.0) * parallax - center.0) * zoom;
+		let rel_y = (point.1 - (self.position.1 + self.shake_offset.1) * parallax - center.1) * zoom;
+
+		let (sin, cos) = angle.to_radians().sin_cos();
+		(center.0 + rel_x * cos - rel_y * sin,
+		 center.1 + rel_x * sin + rel_y * cos)
+	}
+
+	// Maps a world rectangle to the screen, returning the angle it should be rotated by
+	pub fn transform(&self, rect: Rectangle, parallax: f64, viewport: (f64, f64)) -> (Rectangle, f64) {
+		if parallax == 0.0 {
+			return (rect, 0.0);
+		}
+
+		let zoom = 1.0 + (self.zoom - 1.0) * parallax;
+		let angle = (self.rotation + self.shake_angle) * parallax;
+		let center = self.world_to_screen(rect.center(), parallax, viewport);
+
+		(Rectangle::with_size(rect.w * zoom, rect.h * zoom).center_at(center), angle)
+	}
+}
+
+
+// Describes how a ParticleEmitter spawns and animates its particles
+#[derive(Clone, Copy, Debug)]
+pub struct EmitterDescr {
+	// Particles spawned every second while emitting
+	pub rate: f64,
+	// Range of a particle's lifetime in seconds
+	pub lifetime: (f64, f64),
+	// Direction in degrees, 0.0 pointing right and 90.0 pointing down
+	pub direction: f64,
+	// Width of the cone around `direction`, 360.0 for every direction
+	pub spread: f64,
+	pub speed: (f64, f64),
+	// Acceleration applied to every particle, in pixels per second squared
+	pub gravity: (f64, f64),
+	// Fraction of the velocity lost every second
+	pub drag: f64,
+	pub color_start: Color,
+	pub color_end: Color,
+	pub size_start: f64,
+	pub size_end: f64,
+	pub blend: BlendMode,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Particle {
+	pos: (f64, f64),
+	vel: (f64, f64),
+	age: f64,
+	lifetime: f64,
+	alive: bool,
+}
+
+// Fixed-capacity particle pool. Updating and spawning never allocate; when the pool is
+// full, new particles are dropped.
+pub struct ParticleEmitter {
+	pub descr: EmitterDescr,
+	pub position: (f64, f64),
+	pub emitting: bool,
+
+	particles: Vec<Particle>,
+	// Indices of the dead particles, reused by the next spawns
+	free: Vec<usize>,
+	spawn_debt: f64,
+}
+
+impl ParticleEmitter {
+	pub fn new(descr: EmitterDescr, capacity: usize) -> ParticleEmitter {
+		ParticleEmitter {
+			descr: descr,
+			position: (0.0, 0.0),
+			emitting: true,
+			particles: vec![Particle {
+				pos: (0.0, 0.0),
+				vel: (0.0, 0.0),
+				age: 0.0,
+				lifetime: 0.0,
+				alive: false,
+			}; capacity],
+			free: (0..capacity).rev().collect(),
+			spawn_debt: 0.0,
+		}
+	}
+
+	// Number of particles currently alive
+	pub fn live_count(&self) -> usize {
+		self.particles.len() - self.free.len()
+	}
+
+	// Spawns `count` particles at once from the emitter's position
+	pub fn burst(&mut self, count: usize) {
+		for _ in 0..count {
+			self.spawn();
+		}
+	}
+
+	// Moves the emitter, then spawns `count` particles from there
+	pub fn burst_at(&mut self, position: (f64, f64), count: usize) {
+		self.position = position;
+		self.burst(count);
+	}
+
+	fn spawn(&mut self) {
+		let index = match self.free.pop() {
+			Some(index) => index,
+			None => return,
+		};
+
+		let descr = &self.descr;
+		let angle = (descr.direction + (::rand::random::<f64>() - 0.5) * descr.spread).to_radians();
+		let speed = descr.speed.0 + ::rand::random::<f64>() * (descr.speed.1 - descr.speed.0);
+
+		self.particles[index] = Particle {
+			pos: self.position,
+			vel: (angle.cos() * speed, angle.sin() * speed),
+			age: 0.0,
+			lifetime: descr.lifetime.0 + ::rand::random::<f64>() * (descr.lifetime.1 - descr.lifetime.0),
+			alive: true,
+		};
+	}
+
+	pub fn update(&mut self, dt: f64) {
+		if self.emitting {
+			self.spawn_debt += self.descr.rate * dt;
+			while self.spawn_debt >= 1.0 {
+				self.spawn();
+				self.spawn_debt -= 1.0;
+			}
+		} else {
+			self.spawn_debt = 0.0;
+		}
+
+		let damping = (1.0 - self.descr.drag * dt).max(0.0);
+
+		for (index, particle) in self.particles.iter_mut().enumerate() {
+			if !particle.alive {
+				continue;
+			}
+
+			particle.age += dt;
+			if particle.age >= particle.lifetime {
+				particle.alive = false;
+				self.free.push(index);
+				continue;
+			}
+
+			particle.vel.0 = (particle.vel.0 + self.descr.gravity.0 * dt) * damping;
+			particle.vel.1 = (particle.vel.1 + self.descr.gravity.1 * dt) * damping;
+			particle.pos.0 += particle.vel.0 * dt;
+			particle.pos.1 += particle.vel.1 * dt;
+		}
+	}
+
+	pub fn render(&self, queue: &mut RenderQueue, layer: Layer, z: i32) {
+		let descr = &self.descr;
+
+		for particle in self.particles.iter().filter(|p| p.alive) {
+			let t = particle.age / particle.lifetime;
+			let size = descr.size_start + (descr.size_end - descr.size_start) * t;
+
+			queue.fill_rect_ex(layer, z,
+				Rectangle::with_size(size, size).center_at(particle.pos),
+				lerp_color(descr.color_start, descr.color_end, t),
+				descr.blend);
+		}
+	}
+}
+
+// Interpolates between two colors, t going from 0.0 (a) to 1.0 (b)
+fn lerp_color(a: Color, b: Color, t: f64) -> Color {
+	fn rgba(color: Color) -> (f64, f64, f64, f64) {
+		match color {
+			Color::RGB(r, g, b) => (r as f64, g as f64, b as f64, 255.0),
+			Color::RGBA(r, g, b, a) => (r as f64, g as f64, b as f64, a as f64),
+		}
+	}
+
+	let (a, b) = (rgba(a), rgba(b));
+	let lerp = |from: f64, to: f64| (from + (to - from) * t) as u8;
+
+	Color::RGBA(lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2), lerp(a.3, b.3))
 }
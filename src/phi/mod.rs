@@ -1,11 +1,23 @@
 #[macro_use]
 mod events;
+pub mod audio;
+pub mod config;
 pub mod data;
 pub mod gfx;
+pub mod net;
+pub mod pool;
+pub mod rng;
+pub mod settings;
 
-use self::gfx::Sprite;
-use ::sdl2::pixels::Color;
+use self::audio::Audio;
+use self::gfx::{Camera, RenderQueue, Sprite};
+use self::settings::{Settings, WindowMode};
+use ::sdl2::pixels::{Color, PixelFormatEnum};
+use ::sdl2::rect::Rect as SdlRect;
 use ::sdl2::render::Renderer;
+use ::sdl2::surface::Surface;
+use ::sdl2::video::FullscreenType;
+use ::sdl2::VideoSubsystem;
 use ::std::collections::HashMap;
 use ::std::path::Path;
 
@@ -19,41 +31,178 @@ struct_events! {
 		key_right: Right,
 		key_space: Space,
       key_enter: Return,
+      key_f3: F3,
+
+      key_w: W,
+      key_a: A,
+      key_s: S,
+      key_d: D,
+      key_q: Q,
+      key_lshift: LShift,
 
       key_1: Num1,
       key_2: Num2,
-      key_3: Num3
+      key_3: Num3,
+      key_4: Num4,
+      key_5: Num5,
+      key_6: Num6,
+      key_7: Num7,
+      key_8: Num8,
+      key_9: Num9
 	},
 	else: {
 		quit: Quit { .. }
 	}
 }
 
+// Resolution the game logic runs at, whatever the size of the window
+pub const LOGICAL_WIDTH: u32 = 800;
+pub const LOGICAL_HEIGHT: u32 = 600;
+
+// Where the video settings are persisted between runs
+const SETTINGS_PATH: &'static str = "settings.cfg";
+
+// How the logical resolution is fitted inside the window
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scaling {
+	// Scale as much as possible while keeping the aspect ratio, adding black bars
+	Letterbox,
+	// Only scale by whole factors, keeping pixels sharp
+	Integer,
+}
+
 // Bundles the Phi abstraction in a single structure for easier parametrization
 pub struct Phi<'window> {
 	pub events: Events,
 	pub renderer: Renderer<'window>,
+	pub queue: RenderQueue,
+	pub camera: Camera,
+	pub audio: Audio,
 
+   video: VideoSubsystem,
+   settings: Settings,
+   scaling: Scaling,
    cached_fonts: HashMap<(&'static str, i32), ::sdl2_ttf::Font>,
 }
 
 impl<'window> Phi<'window> {
-   fn new(events: Events, renderer: Renderer<'window>) -> Phi<'window> {
+   fn new(events: Events, renderer: Renderer<'window>, video: VideoSubsystem, settings: Settings) -> Phi<'window> {
       ::sdl2_image::init(::sdl2_image::INIT_PNG);
 
-      Phi {
+      let mut phi = Phi {
          events: events,
          renderer: renderer,
+         queue: RenderQueue::new(),
+         camera: Camera::new(),
+         audio: Audio::new(),
+         video: video,
+         settings: settings,
+         scaling: Scaling::Letterbox,
          cached_fonts: HashMap::new(),
-      }
+      };
+
+      phi.apply_scaling();
+      phi.apply_volumes();
+      phi
    }
 
-   // Returns the size of the window (w, h)
+   // Returns the logical size of the screen (w, h), which never changes with the window
 	pub fn output_size(&self) -> (f64, f64) {
-		let (w, h) = self.renderer.output_size().unwrap();
-		(w as f64, h as f64)
+		(LOGICAL_WIDTH as f64, LOGICAL_HEIGHT as f64)
 	}
 
+   // Returns the size of the window in pixels (w, h)
+   pub fn window_size(&self) -> (f64, f64) {
+      let (w, h) = self.renderer.output_size().unwrap();
+      (w as f64, h as f64)
+   }
+
+   pub fn scaling(&self) -> Scaling {
+      self.scaling
+   }
+
+   pub fn set_scaling(&mut self, scaling: Scaling) {
+      self.scaling = scaling;
+      self.apply_scaling();
+   }
+
+   pub fn settings(&self) -> Settings {
+      self.settings
+   }
+
+   // Applies new video settings to the running window and saves them for the next runs
+   pub fn apply_settings(&mut self, settings: Settings) {
+      let old = self.settings;
+
+      // Touching the window makes it flicker, so leave it alone when e.g. only a volume changed
+      if settings.window_mode != old.window_mode || settings.resolution != old.resolution {
+         let window = self.renderer.window_mut().unwrap();
+         let (w, h) = settings.resolution;
+
+         match settings.window_mode {
+            WindowMode::Windowed => {
+               window.set_fullscreen(FullscreenType::Off).unwrap();
+               window.set_size(w, h);
+            },
+            WindowMode::Borderless => {
+               window.set_fullscreen(FullscreenType::Desktop).unwrap();
+            },
+            WindowMode::Fullscreen => {
+               window.set_size(w, h);
+               window.set_fullscreen(FullscreenType::True).unwrap();
+            },
+         }
+      }
+
+      // The renderer uses OpenGL, so its swap interval decides vsync
+      if settings.vsync != old.vsync {
+         self.video.gl_set_swap_interval(if settings.vsync { 1 } else { 0 });
+      }
+
+      self.settings = settings;
+      self.apply_scaling();
+      self.apply_volumes();
+
+      if let Err(err) = settings.save(SETTINGS_PATH) {
+         println!("Could not save the settings: {}", err);
+      }
+   }
+
+   fn apply_volumes(&mut self) {
+      let settings = self.settings;
+      self.audio.set_volumes(
+         settings.master_volume as f64 / 100.0,
+         settings.music_volume as f64 / 100.0,
+         settings.sfx_volume as f64 / 100.0);
+   }
+
+   // Fits the logical resolution inside the window, called again whenever it is resized
+   fn apply_scaling(&mut self) {
+      match self.scaling {
+         Scaling::Letterbox => {
+            // SDL keeps the aspect ratio and centers the picture by itself
+            self.renderer.set_logical_size(LOGICAL_WIDTH, LOGICAL_HEIGHT).unwrap();
+         },
+
+         Scaling::Integer => {
+            // Turn off SDL's own scaling so that it does not override ours on resize
+            self.renderer.set_logical_size(0, 0).unwrap();
+
+            let (win_w, win_h) = self.renderer.output_size().unwrap();
+            let scale = ::std::cmp::max(1,
+               ::std::cmp::min(win_w / LOGICAL_WIDTH, win_h / LOGICAL_HEIGHT));
+
+            // The viewport is expressed in scaled coordinates
+            let offset_x = (win_w as i32 - (LOGICAL_WIDTH * scale) as i32) / 2 / scale as i32;
+            let offset_y = (win_h as i32 - (LOGICAL_HEIGHT * scale) as i32) / 2 / scale as i32;
+
+            self.renderer.set_scale(scale as f32, scale as f32);
+            self.renderer.set_viewport(Some(SdlRect::new_unwrap(
+               offset_x, offset_y, LOGICAL_WIDTH, LOGICAL_HEIGHT)));
+         },
+      }
+   }
+
    // Gets a string and returns a Font sprite
    pub fn ttf_str_sprite(&mut self, text: &str, font_path: &'static str, size: i32, color: Color) -> Option<Sprite> {
       
@@ -91,30 +240,67 @@ pub trait View {
 	fn render(&mut self, context: &mut Phi, elapsed: f64) -> ViewAction;
 }
 
+// Runs `run` with a context which renders to memory rather than to a window, e.g. to simulate
+// games on a machine without a display. Sounds go to SDL's dummy driver.
+pub fn headless<F, T>(run: F) -> T
+where F: FnOnce(&mut Phi) -> T {
+   ::std::env::set_var("SDL_VIDEODRIVER", "dummy");
+   ::std::env::set_var("SDL_AUDIODRIVER", "dummy");
+
+   let sdl_context = ::sdl2::init().unwrap();
+   let video = sdl_context.video().unwrap();
+   let _ttf_context = ::sdl2_ttf::init();
+
+   let surface = Surface::new(LOGICAL_WIDTH, LOGICAL_HEIGHT, PixelFormatEnum::RGBA8888).unwrap();
+   let mut context = Phi::new(
+      Events::new(sdl_context.event_pump().unwrap()),
+      Renderer::from_surface(surface).unwrap(),
+      video,
+      Settings::new());
+
+   run(&mut context)
+}
+
 pub fn spawn<F>(title: &str, init: F)
-where F: Fn(&mut Phi) -> Box<View> {
+where F: FnOnce(&mut Phi) -> Box<View> {
    // Init sdl2
    let sdl_context = ::sdl2::init().unwrap();
    let video = sdl_context.video().unwrap();
    let mut timer = sdl_context.timer().unwrap();	
    let _ttf_context = ::sdl2_ttf::init();
+   // Without an audio subsystem, e.g. on a headless machine, the game simply stays silent
+   let _audio_subsystem = sdl_context.audio().ok();
+
+   let settings = Settings::load(SETTINGS_PATH);
 
    // Create window
-   let window = video.window(title, 800, 600)
-   		.position_centered().opengl().resizable()
-   		.build().unwrap();
+   let mut window_builder = video.window(title, settings.resolution.0, settings.resolution.1);
+   window_builder.position_centered().opengl().resizable();
+
+   match settings.window_mode {
+      WindowMode::Windowed => {},
+      WindowMode::Borderless => { window_builder.fullscreen_desktop(); },
+      WindowMode::Fullscreen => { window_builder.fullscreen(); },
+   }
+
+   let window = window_builder.build().unwrap();
+
+   let mut renderer_builder = window.renderer().accelerated();
+   if settings.vsync {
+      renderer_builder = renderer_builder.present_vsync();
+   }
 
    	// Create context
    	let mut context = Phi::new(
          Events::new(sdl_context.event_pump().unwrap()),
-         window.renderer().accelerated()
-            .build().unwrap());
+         renderer_builder.build().unwrap(),
+         video.clone(),
+         settings);
 
    	// Create default view
    	let mut current_view =  init(&mut context);
 
    	// Frame timing
-   	let interval = 1_000 / 60;
    	let mut before = timer.ticks();
    	let mut last_second = timer.ticks();
    	let mut fps = 0u16;
@@ -125,6 +311,10 @@ where F: Fn(&mut Phi) -> Box<View> {
    		let dt = now - before;
    		let elapsed = dt as f64 / 1_0000.0;
 
+   		// The cap may change at any time from the options menu
+   		let fps_cap = context.settings.fps_cap;
+   		let interval = if fps_cap == 0 { 0 } else { 1_000 / fps_cap };
+
    		if dt < interval {
    			timer.delay(interval - dt);
    			continue;
@@ -134,19 +324,33 @@ where F: Fn(&mut Phi) -> Box<View> {
    		fps += 1;
 
    		if now - last_second > 1_000 {
-   			println!("FPS: {}", fps);
+   			let stats = context.queue.stats();
+   			println!("FPS: {}, draw calls: {}, texture switches: {}",
+   				fps, stats.draw_calls, stats.texture_switches);
    			last_second = now;
    			fps = 0;
    		}
 
    		// Logic and rendering
    		context.events.pump(&mut context.renderer);
+   		context.audio.update(dt as f64 / 1_000.0);
+
+   		if context.events.now.resize.is_some() {
+   			context.apply_scaling();
+   		}
 
    		match current_view.render(&mut context, 0.01) {
-   			ViewAction::None => context.renderer.present(),
+   			ViewAction::None => {
+   				let viewport = context.output_size();
+   				context.queue.flush(&mut context.renderer, &context.camera, viewport);
+   				context.renderer.present();
+   			},
    			ViewAction::Quit => break,
-   			ViewAction::ChangeView(new_view) =>
-   				current_view = new_view,
+   			ViewAction::ChangeView(new_view) => {
+   				context.queue.clear();
+   				context.camera = Camera::new();
+   				current_view = new_view;
+   			},
    		}
    	}
 }
\ No newline at end of file
@@ -0,0 +1,488 @@
+use ::std::cmp;
+use ::std::collections::{HashMap, VecDeque};
+use ::std::io;
+use ::std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use ::std::time::{Duration, Instant};
+
+// Lockstep netplay over UDP. Peers only send each other their player's input for every
+// frame, and each of them simulates a frame once it has the inputs of both players.
+// The simulation must be deterministic, the same inputs leading to the same state on both
+// peers, which they check by exchanging checksums of that state.
+
+// Frames between reading a local input and simulating it. Peers do not have to wait for each
+// other as long as inputs make it across in this time.
+pub const INPUT_DELAY: u32 = 4;
+
+// Seconds without hearing from the peer after which it is considered gone
+const TIMEOUT: u64 = 5;
+// Inputs not acknowledged by the peer yet are sent again in every packet, up to this many
+const MAX_INPUTS_PER_PACKET: usize = 64;
+// Frames for which checksums are kept, waiting for the peer's one to compare them with
+const CHECKSUM_HISTORY: u32 = 256;
+// Sent in place of a checksum's frame before the first frame is simulated
+const NO_CHECKSUM: u32 = ::std::u32::MAX;
+
+// Every packet starts with these bytes, followed by its kind
+const MAGIC: [u8; 2] = [b'A', b'R'];
+const HELLO: u8 = 1;
+const WELCOME: u8 = 2;
+const INPUTS: u8 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+	// Waiting for the peer to show up
+	Connecting,
+	Running,
+	// Both peers simulated this frame, but ended up in different states
+	Desynced(u32),
+	// The peer stopped answering
+	Disconnected,
+}
+
+// One side of a game between two peers. The host plays as the first player and picks the
+// seed of the game, the guest plays as the second one.
+pub struct Lockstep {
+	socket: UdpSocket,
+	// Only known to the host once the guest said hello
+	peer: Option<SocketAddr>,
+	player: usize,
+	seed: u32,
+	status: Status,
+	last_heard: Instant,
+
+	// Next frame to simulate
+	frame: u32,
+	// Local inputs from the frame `local_base` on, kept until they were both simulated and
+	// received by the peer
+	local_base: u32,
+	local_inputs: VecDeque<u16>,
+	// First local input the peer has not received yet
+	acked: u32,
+	// The peer's inputs from the frame `frame` on, without gaps
+	remote_inputs: VecDeque<u16>,
+
+	checksums: HashMap<u32, u64>,
+	remote_checksums: HashMap<u32, u64>,
+}
+
+impl Lockstep {
+	// Waits for a guest on `port`
+	pub fn host(port: u16) -> io::Result<Lockstep> {
+		let socket = try!(UdpSocket::bind(("0.0.0.0", port)));
+		Lockstep::new(socket, None, 0, ::rand::random())
+	}
+
+	// Joins the host at `address`, e.g. "127.0.0.1:7777"
+	pub fn join(address: &str) -> io::Result<Lockstep> {
+		let peer = try!(try!(address.to_socket_addrs()).next()
+			.ok_or(io::Error::new(io::ErrorKind::InvalidInput, "no address to join")));
+		let socket = try!(UdpSocket::bind(("0.0.0.0", 0)));
+		Lockstep::new(socket, Some(peer), 1, 0)
+	}
+
+	fn new(socket: UdpSocket, peer: Option<SocketAddr>, player: usize, seed: u32) -> io::Result<Lockstep> {
+		try!(socket.set_nonblocking(true));
+
+		// Both players do nothing during the first frames, until their first inputs kick in
+		let neutral: VecDeque<u16> = (0..INPUT_DELAY).map(|_| 0).collect();
+
+		Ok(Lockstep {
+			socket: socket,
+			peer: peer,
+			player: player,
+			seed: seed,
+			status: Status::Connecting,
+			last_heard: Instant::now(),
+			frame: 0,
+			local_base: 0,
+			local_inputs: neutral.clone(),
+			acked: INPUT_DELAY,
+			remote_inputs: neutral,
+			checksums: HashMap::new(),
+			remote_checksums: HashMap::new(),
+		})
+	}
+
+	// Where the peer should send its packets, e.g. to join a host listening on any port
+	pub fn local_addr(&self) -> io::Result<SocketAddr> {
+		self.socket.local_addr()
+	}
+
+	// Index of the local player, 0 for the host and 1 for the guest
+	pub fn player(&self) -> usize {
+		self.player
+	}
+
+	// Seed of the game's randomness, the same on both peers once connected
+	pub fn seed(&self) -> u32 {
+		self.seed
+	}
+
+	pub fn status(&self) -> Status {
+		self.status
+	}
+
+	// Next frame to simulate
+	pub fn frame(&self) -> u32 {
+		self.frame
+	}
+
+	// Reads whatever the peer sent, and sends it what it is missing.
+	// Must be called every frame, including while waiting for the peer.
+	pub fn poll(&mut self) {
+		let mut buffer = [0u8; 1024];
+
+		loop {
+			match self.socket.recv_from(&mut buffer) {
+				Ok((len, from)) => self.receive(&buffer[..len], from),
+				Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+				// e.g. the host is not listening yet, which the guest's hellos will find out
+				Err(_) => break,
+			}
+		}
+
+		match self.status {
+			Status::Connecting if self.player == 1 => {
+				let hello = packet(HELLO);
+				self.send(&hello);
+			},
+			Status::Running | Status::Desynced(_) => {
+				if self.last_heard.elapsed() > Duration::from_secs(TIMEOUT) {
+					self.status = Status::Disconnected;
+				} else {
+					let inputs = self.inputs_packet();
+					self.send(&inputs);
+				}
+			},
+			_ => {},
+		}
+	}
+
+	// Queues the local player's input for the frame `INPUT_DELAY` frames after the next one to
+	// simulate. Returns false, dropping the input, when waiting for the peer's inputs.
+	pub fn push_input(&mut self, input: u16) -> bool {
+		let local_end = self.local_base + self.local_inputs.len() as u32;
+		if self.status != Status::Running || local_end > self.frame + INPUT_DELAY {
+			return false;
+		}
+
+		self.local_inputs.push_back(input);
+		true
+	}
+
+	// Inputs of both players for the next frame, by player, once the peer's one has arrived
+	pub fn next_inputs(&self) -> Option<[u16; 2]> {
+		if self.status != Status::Running {
+			return None;
+		}
+
+		let local = self.local_inputs.get((self.frame - self.local_base) as usize);
+		match (local, self.remote_inputs.front()) {
+			(Some(&local), Some(&remote)) =>
+				Some(if self.player == 0 { [local, remote] } else { [remote, local] }),
+			_ => None,
+		}
+	}
+
+	// Moves on to the next frame, once the current one was simulated with `next_inputs`
+	// and led to a state summed up by `checksum`
+	pub fn advance(&mut self, checksum: u64) {
+		let frame = self.frame;
+		self.checksums.insert(frame, checksum);
+		self.compare(frame);
+
+		self.remote_inputs.pop_front();
+		self.frame += 1;
+		self.forget_inputs();
+
+		if frame >= CHECKSUM_HISTORY {
+			self.checksums.remove(&(frame - CHECKSUM_HISTORY));
+			self.remote_checksums.remove(&(frame - CHECKSUM_HISTORY));
+		}
+	}
+
+	fn send(&self, packet: &[u8]) {
+		if let Some(peer) = self.peer {
+			// Lost packets are sent again anyway
+			let _ = self.socket.send_to(packet, peer);
+		}
+	}
+
+	// Every local input the peer is missing, along with the latest checksum
+	fn inputs_packet(&self) -> Vec<u8> {
+		let mut packet = packet(INPUTS);
+		let remote_end = self.frame + self.remote_inputs.len() as u32;
+		let last_frame = self.frame.wrapping_sub(1);
+
+		match self.checksums.get(&last_frame) {
+			Some(&checksum) if self.frame > 0 => {
+				put(&mut packet, last_frame as u64, 4);
+				put(&mut packet, checksum, 8);
+			},
+			_ => {
+				put(&mut packet, NO_CHECKSUM as u64, 4);
+				put(&mut packet, 0, 8);
+			},
+		}
+
+		let first = cmp::max(self.acked, self.local_base);
+		let skipped = (first - self.local_base) as usize;
+		let inputs: Vec<u16> = self.local_inputs.iter()
+			.skip(skipped)
+			.take(MAX_INPUTS_PER_PACKET)
+			.cloned()
+			.collect();
+
+		put(&mut packet, remote_end as u64, 4);
+		put(&mut packet, first as u64, 4);
+		put(&mut packet, inputs.len() as u64, 2);
+		for input in inputs {
+			put(&mut packet, input as u64, 2);
+		}
+
+		packet
+	}
+
+	fn receive(&mut self, data: &[u8], from: SocketAddr) {
+		if data.len() < 3 || data[..2] != MAGIC[..] {
+			return;
+		}
+
+		// Once the peer is known, strangers are ignored
+		if self.peer.map_or(false, |peer| peer != from) {
+			return;
+		}
+
+		let mut reader = Reader { data: data, at: 3 };
+
+		match data[2] {
+			// The host answers every hello, in case its welcome got lost
+			HELLO if self.player == 0 => {
+				self.peer = Some(from);
+				let mut welcome = packet(WELCOME);
+				put(&mut welcome, self.seed as u64, 4);
+				self.send(&welcome);
+
+				if self.status == Status::Connecting {
+					self.status = Status::Running;
+				}
+			},
+			WELCOME if self.player == 1 && self.status == Status::Connecting => {
+				if let Some(seed) = reader.read(4) {
+					self.seed = seed as u32;
+					self.status = Status::Running;
+				}
+			},
+			INPUTS if self.status != Status::Connecting => self.receive_inputs(&mut reader),
+			_ => return,
+		}
+
+		self.last_heard = Instant::now();
+	}
+
+	fn receive_inputs(&mut self, reader: &mut Reader) {
+		let header = (reader.read(4), reader.read(8), reader.read(4), reader.read(4), reader.read(2));
+		let (checksum_frame, checksum, acked, first, count) = match header {
+			(Some(a), Some(b), Some(c), Some(d), Some(e)) => (a as u32, b, c as u32, d as u32, e as u32),
+			_ => return,
+		};
+
+		if checksum_frame != NO_CHECKSUM && checksum_frame + CHECKSUM_HISTORY > self.frame {
+			self.remote_checksums.insert(checksum_frame, checksum);
+			self.compare(checksum_frame);
+		}
+
+		self.acked = cmp::max(self.acked, acked);
+		self.forget_inputs();
+
+		for frame in first..first + count {
+			let input = match reader.read(2) {
+				Some(input) => input as u16,
+				None => return,
+			};
+
+			// Inputs already received are sent again until acknowledged
+			if frame == self.frame + self.remote_inputs.len() as u32 {
+				self.remote_inputs.push_back(input);
+			}
+		}
+	}
+
+	// Checks that both peers reached the same state after `frame`, once both have simulated it
+	fn compare(&mut self, frame: u32) {
+		let checksums = (self.checksums.get(&frame), self.remote_checksums.get(&frame));
+
+		if let (Some(local), Some(remote)) = checksums {
+			if local != remote && self.status == Status::Running {
+				self.status = Status::Desynced(frame);
+			}
+		}
+	}
+
+	// Drops the local inputs which were simulated and which the peer has
+	fn forget_inputs(&mut self) {
+		while self.local_base < cmp::min(self.acked, self.frame) {
+			self.local_inputs.pop_front();
+			self.local_base += 1;
+		}
+	}
+}
+
+// Sums a simulation's state up into a number, which is the same for the same state on every
+// peer. Unlike the standard library's hashers, the FNV-1a hash used is not going to change
+// from one build of the game to the next.
+pub struct Checksum(u64);
+
+impl Checksum {
+	pub fn new() -> Checksum {
+		Checksum(0xcbf2_9ce4_8422_2325)
+	}
+
+	pub fn add_u64(&mut self, value: u64) {
+		for i in 0..8 {
+			self.0 ^= (value >> (8 * i)) & 0xff;
+			self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+		}
+	}
+
+	pub fn add_usize(&mut self, value: usize) {
+		self.add_u64(value as u64);
+	}
+
+	pub fn add_f64(&mut self, value: f64) {
+		self.add_u64(value.to_bits());
+	}
+
+	pub fn value(&self) -> u64 {
+		self.0
+	}
+}
+
+// Starts a packet of the given kind
+fn packet(kind: u8) -> Vec<u8> {
+	vec![MAGIC[0], MAGIC[1], kind]
+}
+
+// Appends the `bytes` lowest bytes of `value`, in little-endian order
+fn put(packet: &mut Vec<u8>, value: u64, bytes: usize) {
+	for i in 0..bytes {
+		packet.push((value >> (8 * i)) as u8);
+	}
+}
+
+// Reads the numbers written by `put`, None once the packet runs out
+struct Reader<'a> {
+	data: &'a [u8],
+	at: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn read(&mut self, bytes: usize) -> Option<u64> {
+		if self.at + bytes > self.data.len() {
+			return None;
+		}
+
+		let value = (0..bytes).fold(0, |value, i| value | (self.data[self.at + i] as u64) << (8 * i));
+		self.at += bytes;
+		Some(value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ::std::net::SocketAddr;
+
+	// A host and a guest past the handshake, along with their addresses. Packets are handed
+	// from one to the other by the tests, which decide which ones get lost.
+	fn connected() -> (Lockstep, SocketAddr, Lockstep, SocketAddr) {
+		let mut host = Lockstep::host(0).unwrap();
+		let host_addr: SocketAddr = format!("127.0.0.1:{}", host.local_addr().unwrap().port()).parse().unwrap();
+		let mut guest = Lockstep::join(&host_addr.to_string()).unwrap();
+		let guest_addr: SocketAddr = format!("127.0.0.1:{}", guest.local_addr().unwrap().port()).parse().unwrap();
+
+		host.receive(&packet(HELLO), guest_addr);
+		let mut welcome = packet(WELCOME);
+		put(&mut welcome, host.seed() as u64, 4);
+		guest.receive(&welcome, host_addr);
+
+		assert_eq!(host.status(), Status::Running);
+		assert_eq!(guest.status(), Status::Running);
+		assert_eq!(guest.seed(), host.seed());
+		(host, host_addr, guest, guest_addr)
+	}
+
+	// The inputs an inputs packet carries, by frame
+	fn carried_inputs(data: &[u8]) -> Vec<(u32, u16)> {
+		let mut reader = Reader { data: data, at: 3 };
+		// Skip the checksum and the acknowledgement
+		for &bytes in &[4, 8, 4] {
+			reader.read(bytes);
+		}
+		let first = reader.read(4).unwrap() as u32;
+		let count = reader.read(2).unwrap() as u32;
+		(first..first + count).map(|frame| (frame, reader.read(2).unwrap() as u16)).collect()
+	}
+
+	#[test]
+	fn packets_read_back_what_was_put() {
+		let mut data = packet(INPUTS);
+		put(&mut data, 0xdead_beef, 4);
+		put(&mut data, ::std::u64::MAX, 8);
+		put(&mut data, 513, 2);
+		assert_eq!(&data[..3], &[b'A', b'R', INPUTS]);
+		assert_eq!(data.len(), 3 + 4 + 8 + 2);
+
+		let mut reader = Reader { data: &data, at: 3 };
+		assert_eq!(reader.read(4), Some(0xdead_beef));
+		assert_eq!(reader.read(8), Some(::std::u64::MAX));
+		assert_eq!(reader.read(2), Some(513));
+		assert_eq!(reader.read(1), None);
+	}
+
+	#[test]
+	fn lost_inputs_are_sent_again_until_acknowledged() {
+		let (mut host, host_addr, mut guest, guest_addr) = connected();
+
+		assert!(host.push_input(1));
+		let lost = host.inputs_packet();
+		assert_eq!(carried_inputs(&lost), vec![(INPUT_DELAY, 1)]);
+
+		assert_eq!(host.next_inputs(), Some([0, 0]));
+		host.advance(0);
+		assert!(host.push_input(2));
+
+		// The next packet carries the lost input along with the new one
+		let resent = host.inputs_packet();
+		assert_eq!(carried_inputs(&resent), vec![(INPUT_DELAY, 1), (INPUT_DELAY + 1, 2)]);
+		guest.receive(&resent, host_addr);
+		guest.receive(&resent, host_addr);
+
+		let remote: Vec<u16> = guest.remote_inputs.iter().cloned().collect();
+		assert_eq!(&remote[INPUT_DELAY as usize..], &[1, 2]);
+
+		// Once the guest acknowledged them, they are not sent anymore
+		host.receive(&guest.inputs_packet(), guest_addr);
+		assert!(carried_inputs(&host.inputs_packet()).is_empty());
+	}
+
+	#[test]
+	fn different_checksums_desync_both_peers() {
+		let (mut host, host_addr, mut guest, guest_addr) = connected();
+
+		host.advance(1);
+		guest.advance(1);
+		guest.receive(&host.inputs_packet(), host_addr);
+		host.receive(&guest.inputs_packet(), guest_addr);
+		assert_eq!(host.status(), Status::Running);
+		assert_eq!(guest.status(), Status::Running);
+
+		host.advance(2);
+		guest.advance(3);
+		guest.receive(&host.inputs_packet(), host_addr);
+		host.receive(&guest.inputs_packet(), guest_addr);
+		assert_eq!(host.status(), Status::Desynced(1));
+		assert_eq!(guest.status(), Status::Desynced(1));
+		assert_eq!(host.next_inputs(), None);
+	}
+}
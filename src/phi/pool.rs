@@ -0,0 +1,232 @@
+use ::std::iter::Enumerate;
+use ::std::slice;
+
+// Refers to an entry of a Pool. A handle stays valid as long as its entry is alive, and is
+// never mistaken for a later entry which reuses the same slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+	index: usize,
+	generation: u32,
+}
+
+struct Slot<T> {
+	// Incremented every time the slot is freed, invalidating the handles to its old value
+	generation: u32,
+	value: Option<T>,
+}
+
+// Index-stable storage for short-lived entities, such as bullets.
+// Removed entries leave their slot free for the next insertion, so that a pool which
+// reached its working size no longer allocates nor moves the live entries around.
+pub struct Pool<T> {
+	slots: Vec<Slot<T>>,
+	// Indices of the free slots, the most recently freed one last
+	free: Vec<usize>,
+	len: usize,
+}
+
+impl<T> Pool<T> {
+	pub fn new() -> Pool<T> {
+		Pool::with_capacity(0)
+	}
+
+	pub fn with_capacity(capacity: usize) -> Pool<T> {
+		Pool {
+			slots: Vec::with_capacity(capacity),
+			free: Vec::with_capacity(capacity),
+			len: 0,
+		}
+	}
+
+	// Number of live entries
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	pub fn insert(&mut self, value: T) -> Handle {
+		self.len += 1;
+
+		match self.free.pop() {
+			Some(index) => {
+				let slot = &mut self.slots[index];
+				slot.value = Some(value);
+				Handle { index: index, generation: slot.generation }
+			},
+			None => {
+				self.slots.push(Slot { generation: 0, value: Some(value) });
+				Handle { index: self.slots.len() - 1, generation: 0 }
+			},
+		}
+	}
+
+	// Removes an entry, None if the handle is stale
+	pub fn remove(&mut self, handle: Handle) -> Option<T> {
+		let removed = match self.slots.get_mut(handle.index) {
+			Some(slot) if slot.generation == handle.generation => slot.value.take(),
+			_ => None,
+		};
+
+		if removed.is_some() {
+			self.free_slot(handle.index);
+		}
+
+		removed
+	}
+
+	pub fn get(&self, handle: Handle) -> Option<&T> {
+		match self.slots.get(handle.index) {
+			Some(slot) if slot.generation == handle.generation => slot.value.as_ref(),
+			_ => None,
+		}
+	}
+
+	pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+		match self.slots.get_mut(handle.index) {
+			Some(slot) if slot.generation == handle.generation => slot.value.as_mut(),
+			_ => None,
+		}
+	}
+
+	// Keeps only the entries for which `keep` returns true, updating them in place
+	pub fn retain<F: FnMut(&mut T) -> bool>(&mut self, mut keep: F) {
+		for index in 0..self.slots.len() {
+			let dead = match self.slots[index].value {
+				Some(ref mut value) => !keep(value),
+				None => false,
+			};
+
+			if dead {
+				self.slots[index].value = None;
+				self.free_slot(index);
+			}
+		}
+	}
+
+	pub fn clear(&mut self) {
+		self.retain(|_| false);
+	}
+
+	pub fn iter<'a>(&'a self) -> Iter<'a, T> {
+		Iter { slots: self.slots.iter().enumerate() }
+	}
+
+	pub fn iter_mut<'a>(&'a mut self) -> IterMut<'a, T> {
+		IterMut { slots: self.slots.iter_mut().enumerate() }
+	}
+
+	fn free_slot(&mut self, index: usize) {
+		self.slots[index].generation = self.slots[index].generation.wrapping_add(1);
+		self.free.push(index);
+		self.len -= 1;
+	}
+}
+
+// Iterates over the live entries of a pool, along with their handles
+pub struct Iter<'a, T: 'a> {
+	slots: Enumerate<slice::Iter<'a, Slot<T>>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+	type Item = (Handle, &'a T);
+
+	fn next(&mut self) -> Option<(Handle, &'a T)> {
+		while let Some((index, slot)) = self.slots.next() {
+			if let Some(ref value) = slot.value {
+				return Some((Handle { index: index, generation: slot.generation }, value));
+			}
+		}
+		None
+	}
+}
+
+pub struct IterMut<'a, T: 'a> {
+	slots: Enumerate<slice::IterMut<'a, Slot<T>>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+	type Item = (Handle, &'a mut T);
+
+	fn next(&mut self) -> Option<(Handle, &'a mut T)> {
+		while let Some((index, slot)) = self.slots.next() {
+			let generation = slot.generation;
+			if let Some(ref mut value) = slot.value {
+				return Some((Handle { index: index, generation: generation }, value));
+			}
+		}
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn removed_entries_leave_stale_handles() {
+		let mut pool = Pool::new();
+		let a = pool.insert("a");
+		let b = pool.insert("b");
+
+		assert_eq!(pool.remove(a), Some("a"));
+		assert_eq!(pool.len(), 1);
+		assert_eq!(pool.get(a), None);
+		assert_eq!(pool.get_mut(a), None);
+		assert_eq!(pool.remove(a), None);
+		assert_eq!(pool.len(), 1);
+		assert_eq!(pool.get(b), Some(&"b"));
+	}
+
+	#[test]
+	fn reused_slots_get_a_new_generation() {
+		let mut pool = Pool::new();
+		let old = pool.insert(1);
+		pool.remove(old);
+
+		// The freed slot is reused rather than the storage growing
+		let new = pool.insert(2);
+		assert_eq!(new.index, old.index);
+		assert!(new.generation != old.generation);
+		assert_eq!(pool.slots.len(), 1);
+
+		assert_eq!(pool.get(old), None);
+		assert_eq!(pool.remove(old), None);
+		assert_eq!(pool.get(new), Some(&2));
+	}
+
+	#[test]
+	fn retain_frees_the_slots_of_dropped_entries() {
+		let mut pool = Pool::with_capacity(4);
+		let handles: Vec<Handle> = (0..4).map(|i| pool.insert(i)).collect();
+
+		pool.retain(|value| {
+			*value *= 10;
+			*value != 10 && *value != 30
+		});
+
+		assert_eq!(pool.len(), 2);
+		assert_eq!(pool.iter().map(|(_, &value)| value).collect::<Vec<_>>(), vec![0, 20]);
+		assert_eq!(pool.get(handles[1]), None);
+		assert_eq!(pool.get(handles[2]), Some(&20));
+
+		// Both freed slots are reused before the storage grows, the last freed first
+		assert_eq!(pool.insert(5).index, handles[3].index);
+		assert_eq!(pool.insert(6).index, handles[1].index);
+		assert_eq!(pool.insert(7).index, 4);
+		assert!(pool.iter_mut().all(|(handle, _)| handle.index < 5));
+	}
+
+	#[test]
+	fn clear_invalidates_every_handle() {
+		let mut pool = Pool::new();
+		let handles: Vec<Handle> = (0..3).map(|i| pool.insert(i)).collect();
+
+		pool.clear();
+		assert!(pool.is_empty());
+		assert!(handles.iter().all(|&handle| pool.get(handle).is_none()));
+		assert_eq!(pool.iter().count(), 0);
+	}
+}
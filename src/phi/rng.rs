@@ -0,0 +1,25 @@
+use ::rand::{Rand, Rng, SeedableRng, XorShiftRng};
+use ::std::cell::RefCell;
+
+// Randomness the game's logic depends on, e.g. where asteroids come from. Unlike
+// `::rand::random`, it can be seeded, so that peers playing over the network see the same
+// game. Purely cosmetic randomness, such as particles, does not need to go through it.
+// Every thread has its own generator, so that simulations can run side by side.
+thread_local!(static RNG: RefCell<XorShiftRng> = RefCell::new(XorShiftRng::new_unseeded()));
+
+// Restarts the sequence of random values, which is the same for the same seed
+pub fn seed(seed: u32) {
+	// A generator seeded with zeroes only ever gives zeroes, so mix in constants
+	RNG.with(|rng| rng.borrow_mut().reseed([seed, !seed, 0x9e37_79b9, 0x2545_f491]));
+}
+
+// Returns a random value, as `::rand::random` would
+pub fn random<T: Rand>() -> T {
+	RNG.with(|rng| rng.borrow_mut().gen())
+}
+
+// Trades the thread's generator for `other`, so that simulations taking turns on one thread
+// each keep a sequence of their own
+pub fn swap(other: &mut XorShiftRng) {
+	RNG.with(|rng| ::std::mem::swap(&mut *rng.borrow_mut(), other));
+}
@@ -0,0 +1,106 @@
+use ::phi::config;
+use ::std::fs::File;
+use ::std::io::{self, Write};
+use ::std::path::Path;
+
+// How the window is shown on the screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindowMode {
+	Windowed,
+	// Fullscreen at the desktop resolution, without changing the display mode
+	Borderless,
+	// Fullscreen at the chosen resolution
+	Fullscreen,
+}
+
+impl WindowMode {
+	pub fn name(self) -> &'static str {
+		match self {
+			WindowMode::Windowed => "windowed",
+			WindowMode::Borderless => "borderless",
+			WindowMode::Fullscreen => "fullscreen",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<WindowMode> {
+		match name {
+			"windowed" => Some(WindowMode::Windowed),
+			"borderless" => Some(WindowMode::Borderless),
+			"fullscreen" => Some(WindowMode::Fullscreen),
+			_ => None,
+		}
+	}
+}
+
+// Video and audio settings, stored as `key = value` lines in a config file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settings {
+	pub window_mode: WindowMode,
+	pub resolution: (u32, u32),
+	pub vsync: bool,
+	// Maximum frames per second, 0 for no limit
+	pub fps_cap: u32,
+	// Volumes, in percent
+	pub master_volume: u32,
+	pub music_volume: u32,
+	pub sfx_volume: u32,
+}
+
+impl Settings {
+	pub fn new() -> Settings {
+		Settings {
+			window_mode: WindowMode::Windowed,
+			resolution: (800, 600),
+			vsync: false,
+			fps_cap: 60,
+			master_volume: 100,
+			music_volume: 70,
+			sfx_volume: 100,
+		}
+	}
+
+	// Reads the settings from a file, falling back to the defaults for anything missing or invalid
+	pub fn load<P: AsRef<Path>>(path: P) -> Settings {
+		let mut settings = Settings::new();
+
+		let sections = match config::load(path) {
+			Ok(sections) => sections,
+			Err(_) => return settings,
+		};
+
+		for section in &sections {
+			if let Some(mode) = section.get("window_mode").and_then(WindowMode::from_name) {
+				settings.window_mode = mode;
+			}
+
+			if let Some(resolution) = section.get("resolution") {
+				let mut dims = resolution.splitn(2, 'x').map(|d| d.trim().parse::<u32>());
+				if let (Some(Ok(w)), Some(Ok(h))) = (dims.next(), dims.next()) {
+					settings.resolution = (w, h);
+				}
+			}
+
+			settings.vsync = section.parse_or("vsync", settings.vsync);
+			settings.fps_cap = section.parse_or("fps_cap", settings.fps_cap);
+
+			let percent = |volume: u32| ::std::cmp::min(volume, 100);
+			settings.master_volume = percent(section.parse_or("master_volume", settings.master_volume));
+			settings.music_volume = percent(section.parse_or("music_volume", settings.music_volume));
+			settings.sfx_volume = percent(section.parse_or("sfx_volume", settings.sfx_volume));
+		}
+
+		settings
+	}
+
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+		let mut file = try!(File::create(path));
+		try!(writeln!(file, "window_mode = {}", self.window_mode.name()));
+		try!(writeln!(file, "resolution = {}x{}", self.resolution.0, self.resolution.1));
+		try!(writeln!(file, "vsync = {}", self.vsync));
+		try!(writeln!(file, "fps_cap = {}", self.fps_cap));
+		try!(writeln!(file, "master_volume = {}", self.master_volume));
+		try!(writeln!(file, "music_volume = {}", self.music_volume));
+		try!(writeln!(file, "sfx_volume = {}", self.sfx_volume));
+		Ok(())
+	}
+}
@@ -0,0 +1,414 @@
+use phi::Phi;
+use phi::data::Rectangle;
+use phi::gfx::{DrawParams, Layer, Renderable, Sprite};
+use phi::net::Checksum;
+use sdl2::pixels::Color;
+use sdl2::render::BlendMode;
+use std::f64::consts::PI;
+use views::bullets::{BulletKind, Target, Team};
+use views::patterns::{self, Pattern, PatternEmitter};
+
+
+/// The boss is drawn above enemies and asteroids.
+pub const BOSS_Z: i32 = 3;
+
+/// Size of the boss' hull, which hurts the player but cannot be damaged.
+const HULL_W: f64 = 160.0;
+const HULL_H: f64 = 150.0;
+/// Pixels traveled by the boss every second while entering the screen.
+const ENTRY_SPEED: f64 = 60.0;
+/// Distance between the boss' center and the right edge of the screen once it has arrived.
+const RESTING_X: f64 = 130.0;
+/// Amplitude, in pixels, and speed, in radians per second, of the boss' bobbing.
+const BOB_AMPLITUDE: f64 = 120.0;
+const BOB_SPEED: f64 = 0.6;
+
+/// Timing of the sweeping laser, in seconds: it shows where it aims while warming up,
+/// then sweeps and finally cools down.
+const LASER_WARMUP: f64 = 1.0;
+const LASER_SWEEP: f64 = 3.0;
+const LASER_COOLDOWN: f64 = 1.5;
+/// Half the angle swept by the laser, in degrees, around the direction it faces.
+const LASER_SWEEP_ANGLE: f64 = 35.0;
+const LASER_WIDTH: f64 = 18.0;
+
+/// Seconds between two summons, and asteroids summoned each time.
+const SUMMON_INTERVAL: f64 = 2.5;
+const SUMMON_COUNT: usize = 2;
+
+
+/// What a part of the boss does while it is intact.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PartKind {
+    /// Destroying the core defeats the boss.
+    Core,
+    /// Fires the bullet curtains.
+    Turret,
+    /// Fires the sweeping laser.
+    Emitter,
+}
+
+/// A hittable part of the boss, placed relative to its center.
+pub struct BossPart {
+    /// Unique for the whole game, shared with asteroids and enemies.
+    pub id: usize,
+    pub kind: PartKind,
+    pub hp: f64,
+    max_hp: f64,
+    offset: (f64, f64),
+    size: (f64, f64),
+    /// Whether the part's destruction was already reported.
+    destroyed: bool,
+    /// The pattern fired by turrets during the current phase.
+    emitter: Option<PatternEmitter>,
+}
+
+impl BossPart {
+    fn intact(&self) -> bool {
+        self.hp > 0.0
+    }
+}
+
+
+/// The attack patterns the boss goes through as its health drops.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    /// The turrets fire fans of bullets.
+    Curtain,
+    /// The emitter sweeps a laser across the screen.
+    Sweep,
+    /// The boss calls asteroids in, while the turrets keep firing slower curtains.
+    Summon,
+}
+
+impl Phase {
+    /// The phase the boss is in with `health` between 0.0 and 1.0 left.
+    fn at(health: f64) -> Phase {
+        if health > 2.0 / 3.0 { Phase::Curtain }
+        else if health > 1.0 / 3.0 { Phase::Sweep }
+        else { Phase::Summon }
+    }
+
+    /// What the turrets fire during the phase, None if they hold their fire.
+    fn turret_pattern(self) -> Option<Pattern> {
+        match self {
+            Phase::Curtain => Some(patterns::CURTAIN),
+            Phase::Sweep => None,
+            Phase::Summon => Some(patterns::RING.with_rate(0.5)),
+        }
+    }
+}
+
+
+/// A laser fired by the boss, which hurts the player once it is warm.
+#[derive(Clone, Copy, Debug)]
+pub struct Laser {
+    pub origin: (f64, f64),
+    /// In radians, 0 pointing right.
+    pub angle: f64,
+    pub length: f64,
+    pub width: f64,
+    /// While warming up, the laser only shows where it is going to be.
+    pub warm: bool,
+}
+
+impl Laser {
+    pub fn direction(&self) -> (f64, f64) {
+        (self.angle.cos(), self.angle.sin())
+    }
+
+    /// Whether the laser burns through `rect`.
+    pub fn hits(&self, rect: Rectangle) -> bool {
+        // Widen the rectangle rather than casting a thick ray.
+        let rect = Rectangle {
+            x: rect.x - self.width / 2.0,
+            y: rect.y - self.width / 2.0,
+            w: rect.w + self.width,
+            h: rect.h + self.width,
+        };
+
+        self.warm && rect.raycast(self.origin, self.direction())
+            .map_or(false, |distance| distance <= self.length)
+    }
+
+    /// Draw the laser by stretching `sprite` along it.
+    pub fn render<T: Renderable>(&self, phi: &mut Phi, sprite: &T) {
+        let width = if self.warm { self.width } else { 2.0 };
+        let params = DrawParams::new()
+            .rotated(self.angle.to_degrees())
+            .around((0.0, width / 2.0))
+            .with_blend(BlendMode::Add)
+            .with_opacity(if self.warm { 1.0 } else { 0.5 });
+
+        phi.queue.sprite_ex(Layer::Entities, BOSS_Z + 1, sprite, Rectangle {
+            x: self.origin.0,
+            y: self.origin.1 - width / 2.0,
+            w: self.length,
+            h: width,
+        }, params);
+    }
+}
+
+
+/// What the boss wants the game to spawn after an update.
+pub struct BossOrders {
+    /// Number of asteroids to bring in.
+    pub summon: usize,
+    /// Centers of the parts destroyed since the last update.
+    pub destroyed: Vec<(f64, f64)>,
+}
+
+
+pub struct Boss {
+    center: (f64, f64),
+    parts: Vec<BossPart>,
+    sprite: Sprite,
+    /// Seconds since the boss has arrived at its resting position, None while entering.
+    time: Option<f64>,
+    phase: Phase,
+    /// Seconds since the current phase began.
+    phase_time: f64,
+    /// Time before the next summon.
+    summon_cooldown: f64,
+    laser: Option<Laser>,
+    total_hp: f64,
+}
+
+impl Boss {
+    /// Spawn the boss right past the right edge of the screen.
+    /// `sprite` is drawn over its hull, and `ids` gives every part its unique id.
+    pub fn new<F>(sprite: Sprite, area: (f64, f64), ids: &mut F) -> Boss
+        where F: FnMut() -> usize
+    {
+        let mut part = |kind: PartKind, hp: f64, offset: (f64, f64), size: (f64, f64)| BossPart {
+            id: ids(),
+            kind: kind,
+            hp: hp,
+            max_hp: hp,
+            offset: offset,
+            size: size,
+            destroyed: false,
+            emitter: if kind == PartKind::Turret {
+                Phase::Curtain.turret_pattern().map(PatternEmitter::new)
+            } else {
+                None
+            },
+        };
+
+        let parts = vec![
+            part(PartKind::Turret, 12.0, (-30.0, -65.0), (36.0, 28.0)),
+            part(PartKind::Turret, 12.0, (-30.0, 65.0), (36.0, 28.0)),
+            part(PartKind::Emitter, 16.0, (-80.0, 0.0), (30.0, 30.0)),
+            part(PartKind::Core, 30.0, (0.0, 0.0), (44.0, 44.0)),
+        ];
+
+        let total_hp = parts.iter().fold(0.0, |sum, part| sum + part.max_hp);
+
+        Boss {
+            center: (area.0 + HULL_W, area.1 / 2.0),
+            parts: parts,
+            sprite: sprite,
+            time: None,
+            phase: Phase::Curtain,
+            phase_time: 0.0,
+            summon_cooldown: 0.0,
+            laser: None,
+            total_hp: total_hp,
+        }
+    }
+
+    /// Health left, from 0.0 to 1.0, summed over every part.
+    pub fn health(&self) -> f64 {
+        self.parts.iter().fold(0.0, |sum, part| sum + part.hp.max(0.0)) / self.total_hp
+    }
+
+    /// Id of the core, whose destruction defeats the boss.
+    pub fn core_id(&self) -> usize {
+        self.parts.iter().find(|part| part.kind == PartKind::Core).unwrap().id
+    }
+
+    /// Adds the position, the phase and its timers, and every part's health and emitter.
+    pub fn add_to(&self, checksum: &mut Checksum) {
+        checksum.add_f64(self.center.0);
+        checksum.add_f64(self.center.1);
+        checksum.add_f64(self.time.unwrap_or(-1.0));
+        checksum.add_usize(self.phase as usize);
+        checksum.add_f64(self.phase_time);
+        checksum.add_f64(self.summon_cooldown);
+
+        for part in &self.parts {
+            checksum.add_usize(part.id);
+            checksum.add_f64(part.hp);
+            if let Some(ref emitter) = part.emitter {
+                emitter.add_to(checksum);
+            }
+        }
+    }
+
+    pub fn defeated(&self) -> bool {
+        self.parts.iter().any(|part| part.kind == PartKind::Core && !part.intact())
+    }
+
+    /// The hull, which the player must not touch.
+    pub fn rect(&self) -> Rectangle {
+        Rectangle::with_size(HULL_W, HULL_H).center_at(self.center)
+    }
+
+    fn part_rect(&self, part: &BossPart) -> Rectangle {
+        let center = (self.center.0 + part.offset.0, self.center.1 + part.offset.1);
+        Rectangle::with_size(part.size.0, part.size.1).center_at(center)
+    }
+
+    fn part_center(&self, kind: PartKind) -> Option<(f64, f64)> {
+        self.parts.iter()
+            .find(|part| part.kind == kind && part.intact())
+            .map(|part| (self.center.0 + part.offset.0, self.center.1 + part.offset.1))
+    }
+
+    /// Describes the intact parts to the bullets that may aim at them.
+    pub fn targets(&self) -> Vec<Target> {
+        self.parts.iter()
+            .filter(|part| part.intact())
+            .map(|part| Target { id: part.id, rect: self.part_rect(part) })
+            .collect()
+    }
+
+    /// Every part which bullets may damage, along with its id and current rectangle.
+    pub fn parts_mut<'a>(&'a mut self) -> Box<Iterator<Item=(usize, Rectangle, &'a mut f64)> + 'a> {
+        let center = self.center;
+        Box::new(self.parts.iter_mut().map(move |part| {
+            let part_center = (center.0 + part.offset.0, center.1 + part.offset.1);
+            (part.id, Rectangle::with_size(part.size.0, part.size.1).center_at(part_center), &mut part.hp)
+        }))
+    }
+
+    /// Damage the part with the given id, if it belongs to the boss.
+    pub fn damage(&mut self, id: usize, damage: f64) {
+        for part in self.parts.iter_mut().filter(|part| part.id == id) {
+            part.hp -= damage;
+        }
+    }
+
+    /// The laser, if one is being fired.
+    pub fn laser(&self) -> Option<Laser> {
+        self.laser
+    }
+
+    pub fn update<F>(&mut self, player: (f64, f64), area: (f64, f64), dt: f64, mut fire: F) -> BossOrders
+        where F: FnMut(BulletKind)
+    {
+        let mut orders = BossOrders {
+            summon: 0,
+            destroyed: vec![],
+        };
+
+        for i in 0..self.parts.len() {
+            if !self.parts[i].intact() && !self.parts[i].destroyed {
+                self.parts[i].destroyed = true;
+                let rect = self.part_rect(&self.parts[i]);
+                orders.destroyed.push(rect.center());
+            }
+        }
+
+        // Enter the screen, then bob up and down in front of the player.
+        let resting_x = area.0 - RESTING_X;
+        let time = match self.time {
+            None => {
+                self.center.0 = (self.center.0 - ENTRY_SPEED * dt).max(resting_x);
+                if self.center.0 == resting_x {
+                    self.time = Some(0.0);
+                }
+                return orders;
+            },
+            Some(time) => time + dt,
+        };
+        self.time = Some(time);
+        self.center.1 = area.1 / 2.0 + BOB_AMPLITUDE * (time * BOB_SPEED).sin();
+
+        let phase = Phase::at(self.health());
+        if phase != self.phase {
+            self.phase = phase;
+            self.phase_time = 0.0;
+            self.summon_cooldown = 0.0;
+            self.laser = None;
+        }
+        self.phase_time += dt;
+
+        // Without its emitter, the boss falls back to its curtains instead of the laser.
+        let emitter = self.part_center(PartKind::Emitter);
+        let turret_pattern = match (self.phase, emitter) {
+            (Phase::Sweep, None) => Phase::Curtain.turret_pattern(),
+            (phase, _) => phase.turret_pattern(),
+        };
+
+        let center = self.center;
+        for part in self.parts.iter_mut().filter(|part| part.intact()) {
+            if let (Some(emitter), Some(pattern)) = (part.emitter.as_mut(), turret_pattern) {
+                emitter.set_pattern(pattern);
+
+                // Turrets fire from their front.
+                let origin = (center.0 + part.offset.0 - part.size.0 / 2.0, center.1 + part.offset.1);
+                emitter.update(origin, player, Team::Enemy, dt, &mut fire);
+            }
+        }
+
+        if self.phase == Phase::Sweep {
+            self.laser = emitter.and_then(|origin| self.sweep(origin, area));
+        }
+
+        if self.phase == Phase::Summon {
+            self.summon_cooldown -= dt;
+            if self.summon_cooldown <= 0.0 {
+                self.summon_cooldown = SUMMON_INTERVAL;
+                orders.summon = SUMMON_COUNT;
+            }
+        }
+
+        orders
+    }
+
+    /// The laser during the current sweep, or None while it cools down.
+    fn sweep(&self, origin: (f64, f64), area: (f64, f64)) -> Option<Laser> {
+        let cycle = LASER_WARMUP + LASER_SWEEP + LASER_COOLDOWN;
+        let t = self.phase_time % cycle;
+
+        // Sweep from one side to the other, alternating directions every cycle.
+        let progress = ((t - LASER_WARMUP) / LASER_SWEEP).max(0.0).min(1.0);
+        let direction = if (self.phase_time / cycle) as usize % 2 == 0 { 1.0 } else { -1.0 };
+        let offset = LASER_SWEEP_ANGLE * (2.0 * progress - 1.0) * direction;
+
+        if t > LASER_WARMUP + LASER_SWEEP {
+            None
+        } else {
+            Some(Laser {
+                origin: origin,
+                angle: PI + offset.to_radians(),
+                length: area.0,
+                width: LASER_WIDTH,
+                warm: t > LASER_WARMUP,
+            })
+        }
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        // A giant version of the enemies' ships, facing left.
+        phi.queue.sprite_ex(Layer::Entities, BOSS_Z, &self.sprite, self.rect(),
+            DrawParams::new().flipped(true, false).tinted(Color::RGB(200, 120, 255)));
+
+        // Parts go from white to red as they take damage, and turn dark once destroyed.
+        for part in &self.parts {
+            let color = if part.intact() {
+                let health = part.hp / part.max_hp;
+                let fade = (255.0 * health) as u8;
+                match part.kind {
+                    PartKind::Core => Color::RGB(255, fade, 255),
+                    _ => Color::RGB(255, fade, fade),
+                }
+            } else {
+                Color::RGB(50, 40, 40)
+            };
+
+            phi.queue.fill_rect(Layer::Entities, BOSS_Z, self.part_rect(part), color);
+        }
+    }
+}
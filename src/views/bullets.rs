@@ -1,218 +1,884 @@
 use phi::Phi;
+use phi::config::{self, Section};
 use phi::data::Rectangle;
+use phi::gfx::{DrawParams, Layer, Renderable};
+use phi::net::Checksum;
+use views::trajectory::{self, Linear, Rotated, Scaled, Sum, Trajectory};
 use sdl2::pixels::Color;
+use sdl2::render::BlendMode;
+use std::f64::consts::PI;
+use std::io;
+use std::path::Path;
 
 
-/// Pixels traveled horizontally by a bullet every second.
+/// Pixels traveled by a bullet every second, unless its weapon says otherwise.
 pub const BULLET_SPEED: f64 = 240.0;
 pub const BULLET_W: f64 = 8.0;
 pub const BULLET_H: f64 = 4.0;
+/// Bullets are drawn above the player but below asteroids.
+pub const BULLET_Z: i32 = 1;
+
+
+/// Something in the world which bullets may react to, e.g. an asteroid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Target {
+    /// Identifies the target across frames, so that it can be followed.
+    pub id: usize,
+    pub rect: Rectangle,
+}
+
+
+/// Side a bullet was fired by. Bullets only hurt the other side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Team {
+    /// Hits enemies and asteroids.
+    Player,
+    /// Hits the player.
+    Enemy,
+}
+
+
+/// How a bullet affects what it hits.
+#[derive(Clone, Debug)]
+pub struct Impact {
+    pub team: Team,
+    pub damage: f64,
+    /// Number of additional targets the bullet can go through.
+    pub pierce: usize,
+    /// Index of the player who fired the bullet, if a player did.
+    pub owner: Option<usize>,
+    /// Targets already hit, which the bullet will not damage again.
+    hits: Vec<usize>,
+}
+
+impl Impact {
+    pub fn new(team: Team, damage: f64, pierce: usize) -> Impact {
+        Impact {
+            team: team,
+            damage: damage,
+            pierce: pierce,
+            owner: None,
+            hits: vec![],
+        }
+    }
+}
 
 
 pub trait Bullet {
-    /// Update the bullet.
-    /// If the bullet should be destroyed, e.g. because it has left the screen,
-    /// then return `None`.
-    /// Otherwise, return `Some(update_bullet)`.
-    fn update(self: Box<Self>, phi: &mut Phi, dt: f64) -> Option<Box<Bullet>>;
+    /// Update the bullet in place.
+    /// `area` is the size of the playing field, which bullets leave through its edges.
+    /// `targets` holds every entity currently in the world which the bullet may aim at.
+    /// Return whether the bullet is still alive, i.e. `false` if it should be destroyed,
+    /// e.g. because it has left the screen.
+    fn update(&mut self, area: (f64, f64), targets: &[Target], dt: f64) -> bool;
 
     /// Render the bullet to the screen.
     fn render(&self, phi: &mut Phi);
 
     /// Return the bullet's bounding box.
     fn rect(&self) -> Rectangle;
+
+    fn impact(&self) -> &Impact;
+    fn impact_mut(&mut self) -> &mut Impact;
+
+    fn team(&self) -> Team {
+        self.impact().team
+    }
+
+    /// Damage dealt to every target hit.
+    fn damage(&self) -> f64 {
+        self.impact().damage
+    }
+
+    /// Whether the bullet may damage `target`, i.e. has not already gone through it.
+    fn can_hit(&self, target: usize) -> bool {
+        !self.impact().hits.contains(&target)
+    }
+
+    /// Record that the bullet hit `target`.
+    /// Return whether the bullet keeps going, i.e. had pierce left.
+    fn register_hit(&mut self, target: usize) -> bool {
+        let impact = self.impact_mut();
+        impact.hits.push(target);
+
+        if impact.pierce == 0 {
+            false
+        } else {
+            impact.pierce -= 1;
+            true
+        }
+    }
+}
+
+
+/// Whether a bullet is still, at least partly, inside of the playing field.
+fn in_area(rect: Rectangle, area: (f64, f64)) -> bool {
+    let (w, h) = area;
+    !(rect.x > w || rect.x < -rect.w ||
+      rect.y > h || rect.y < -rect.h)
 }
 
 
 pub struct RectBullet {
     rect: Rectangle,
+    vel: (f64, f64),
+    /// Number of times the bullet can still bounce off the edges of the screen.
+    bounces: usize,
+    impact: Impact,
 }
 
 impl Bullet for RectBullet {
-    fn update(mut self: Box<Self>, phi: &mut Phi, dt: f64) -> Option<Box<Bullet>> {
-        let (w, _) = phi.output_size();
-        self.rect.x += BULLET_SPEED * dt;
-
-        // If the bullet has left the screen, then delete it.
-        if self.rect.x > w {
-            None
+    fn update(&mut self, area: (f64, f64), _targets: &[Target], dt: f64) -> bool {
+        let (w, h) = area;
+        self.rect.x += self.vel.0 * dt;
+        self.rect.y += self.vel.1 * dt;
+
+        // Ricochet off the top and bottom edges, and off whichever side edge the bullet is
+        // heading towards, while bounces are left.
+        let side_edge = if self.vel.0 > 0.0 {
+            self.rect.x + self.rect.w > w
         } else {
-            Some(self)
+            self.rect.x < 0.0 && self.vel.0 < 0.0
+        };
+
+        if self.bounces > 0 {
+            if (self.rect.y < 0.0 && self.vel.1 < 0.0) ||
+               (self.rect.y + self.rect.h > h && self.vel.1 > 0.0) {
+                self.vel.1 = -self.vel.1;
+                self.bounces -= 1;
+            } else if side_edge {
+                self.vel.0 = -self.vel.0;
+                self.bounces -= 1;
+            }
         }
+
+        // If the bullet has left the screen, then delete it.
+        in_area(self.rect, area)
     }
 
     fn render(&self, phi: &mut Phi) {
         // We will render this kind of bullet in yellow.
-        phi.renderer.set_draw_color(Color::RGB(230, 230, 30));
-        phi.renderer.fill_rect(self.rect.to_sdl().unwrap());
+        phi.queue.fill_rect(Layer::Entities, BULLET_Z, self.rect, Color::RGB(230, 230, 30));
     }
 
     fn rect(&self) -> Rectangle {
         self.rect
     }
+
+    fn impact(&self) -> &Impact {
+        &self.impact
+    }
+
+    fn impact_mut(&mut self) -> &mut Impact {
+        &mut self.impact
+    }
 }
 
 
-pub struct SineBullet {
-    pos_x: f64,
-    origin_y: f64,
-    amplitude: f64,
-    angular_vel: f64,
+/// Bullet which follows a trajectory from the point where it was fired.
+pub struct PathBullet<T: Trajectory> {
+    origin: (f64, f64),
+    path: T,
     total_time: f64,
+    impact: Impact,
 }
 
-impl Bullet for SineBullet {
-    fn update(mut self: Box<Self>, phi: &mut Phi, dt: f64) -> Option<Box<Bullet>> {
+impl<T: Trajectory> Bullet for PathBullet<T> {
+    fn update(&mut self, area: (f64, f64), _targets: &[Target], dt: f64) -> bool {
         self.total_time += dt;
-        self.pos_x += BULLET_SPEED * dt;
-
-        // If the bullet has left the screen, then delete it.
-        let (w, _) = phi.output_size();
-
-        if self.rect().x > w {
-            None
-        } else {
-            Some(self)
-        }
+        in_area(self.rect(), area)
     }
 
     fn render(&self, phi: &mut Phi) {
-        // We will render this kind of bullet in yellow.
-        phi.renderer.set_draw_color(Color::RGB(230, 230, 30));
-        phi.renderer.fill_rect(self.rect().to_sdl().unwrap());
+        // We will render this kind of bullet in yellow, or in pink when fired by enemies.
+        let color = match self.impact.team {
+            Team::Player => Color::RGB(230, 230, 30),
+            Team::Enemy => Color::RGB(255, 70, 170),
+        };
+        phi.queue.fill_rect(Layer::Entities, BULLET_Z, self.rect(), color);
     }
 
     fn rect(&self) -> Rectangle {
-        let dy = self.amplitude * f64::sin(self.angular_vel * self.total_time);
+        let (dx, dy) = self.path.offset(self.total_time);
         Rectangle {
-            x: self.pos_x,
-            y: self.origin_y + dy,
+            x: self.origin.0 + dx,
+            y: self.origin.1 + dy,
             w: BULLET_W,
             h: BULLET_H,
         }
     }
+
+    fn impact(&self) -> &Impact {
+        &self.impact
+    }
+
+    fn impact_mut(&mut self) -> &mut Impact {
+        &mut self.impact
+    }
 }
 
 
-/// Bullet which follows a vertical trajectory given by:
-///     a * ((t / b)^3 - (t / b)^2)
-pub struct DivergentBullet {
-    pos_x: f64,
-    origin_y: f64,
-    a: f64, // Influences the bump's height
-    b: f64, // Influences the bump's width
-    total_time: f64,
+
+/// Bullet which steers towards the nearest target, retargeting when it is destroyed.
+pub struct HomingBullet {
+    center: (f64, f64),
+    /// Direction of travel, in radians.
+    heading: f64,
+    speed: f64,
+    /// Maximum change of heading, in radians per second.
+    turn_rate: f64,
+    /// Direction the bullet was launched in, as a unit vector.
+    launch: (f64, f64),
+    /// Time left before the bullet fizzles out.
+    lifetime: f64,
+    target: Option<usize>,
+    impact: Impact,
 }
 
-impl Bullet for DivergentBullet {
-    fn update(mut self: Box<Self>, phi: &mut Phi, dt: f64) -> Option<Box<Bullet>> {
-        self.total_time += dt;
-        self.pos_x += BULLET_SPEED * dt;
+impl HomingBullet {
+    fn nearest(&self, targets: &[Target]) -> Option<Target> {
+        let distance = |target: &Target| {
+            let (x, y) = target.rect.center();
+            (x - self.center.0).powi(2) + (y - self.center.1).powi(2)
+        };
+
+        targets.iter()
+            // Only consider targets ahead of the bullet's launch direction.
+            .filter(|target| {
+                let (x, y) = target.rect.center();
+                (x - self.center.0) * self.launch.0 + (y - self.center.1) * self.launch.1 > 0.0
+            })
+            .fold(None, |nearest: Option<&Target>, target| match nearest {
+                Some(best) if distance(best) <= distance(target) => Some(best),
+                _ => Some(target),
+            })
+            .cloned()
+    }
+}
 
-        // If the bullet has left the screen, then delete it.
-        let (w, h) = phi.output_size();
-        let rect = self.rect();
+impl Bullet for HomingBullet {
+    fn update(&mut self, area: (f64, f64), targets: &[Target], dt: f64) -> bool {
+        self.lifetime -= dt;
 
-        if rect.x > w || rect.x < 0.0 ||
-           rect.y > h || rect.y < 0.0 {
-            None
-        } else {
-            Some(self)
+        // Keep following the current target while it exists, otherwise pick a new one.
+        let target = self.target
+            .and_then(|id| targets.iter().find(|target| target.id == id).cloned())
+            .or_else(|| self.nearest(targets));
+        self.target = target.map(|target| target.id);
+
+        if let Some(target) = target {
+            let (x, y) = target.rect.center();
+            let wanted = (y - self.center.1).atan2(x - self.center.0);
+
+            // Turn by the shortest way, at most `turn_rate * dt`.
+            let mut delta = wanted - self.heading;
+            while delta > PI { delta -= 2.0 * PI; }
+            while delta < -PI { delta += 2.0 * PI; }
+
+            let max_turn = self.turn_rate * dt;
+            self.heading += delta.max(-max_turn).min(max_turn);
         }
+
+        self.center.0 += self.speed * self.heading.cos() * dt;
+        self.center.1 += self.speed * self.heading.sin() * dt;
+
+        // If the bullet has burnt out or left the screen, then delete it.
+        self.lifetime > 0.0 && in_area(self.rect(), area)
     }
 
     fn render(&self, phi: &mut Phi) {
-        // We will render this kind of bullet in yellow.
-        phi.renderer.set_draw_color(Color::RGB(230, 230, 30));
-        phi.renderer.fill_rect(self.rect().to_sdl().unwrap());
+        // Missiles are orange, to tell them apart from regular bullets.
+        phi.queue.fill_rect(Layer::Entities, BULLET_Z, self.rect(), Color::RGB(240, 140, 30));
     }
 
     fn rect(&self) -> Rectangle {
-        let dy = self.a *
-                    ((self.total_time / self.b).powi(3) -
-                     (self.total_time / self.b).powi(2));
+        Rectangle::with_size(BULLET_W, BULLET_H).center_at(self.center)
+    }
 
-        Rectangle {
-            x: self.pos_x,
-            y: self.origin_y + dy,
-            w: BULLET_W,
-            h: BULLET_H,
+    fn impact(&self) -> &Impact {
+        &self.impact
+    }
+
+    fn impact_mut(&mut self) -> &mut Impact {
+        &mut self.impact
+    }
+}
+
+
+
+/// Every kind of bullet, stored by value so that firing one allocates nothing.
+pub enum BulletKind {
+    Rect(RectBullet),
+    /// Flies in a straight line, e.g. when fired by enemies.
+    Straight(PathBullet<Linear>),
+    Sine(PathBullet<Sum<Linear, Rotated<trajectory::Sine>>>),
+    Divergent(PathBullet<Sum<Linear, Rotated<trajectory::Divergent>>>),
+    Spiral(PathBullet<Sum<Linear, Rotated<Scaled<trajectory::Spiral>>>>),
+    ZigZag(PathBullet<Sum<Linear, Rotated<Scaled<trajectory::ZigZag>>>>),
+    Homing(HomingBullet),
+}
+
+impl BulletKind {
+    fn as_bullet(&self) -> &Bullet {
+        match *self {
+            BulletKind::Rect(ref bullet) => bullet,
+            BulletKind::Straight(ref bullet) => bullet,
+            BulletKind::Sine(ref bullet) => bullet,
+            BulletKind::Divergent(ref bullet) => bullet,
+            BulletKind::Spiral(ref bullet) => bullet,
+            BulletKind::ZigZag(ref bullet) => bullet,
+            BulletKind::Homing(ref bullet) => bullet,
+        }
+    }
+
+    fn as_bullet_mut(&mut self) -> &mut Bullet {
+        match *self {
+            BulletKind::Rect(ref mut bullet) => bullet,
+            BulletKind::Straight(ref mut bullet) => bullet,
+            BulletKind::Sine(ref mut bullet) => bullet,
+            BulletKind::Divergent(ref mut bullet) => bullet,
+            BulletKind::Spiral(ref mut bullet) => bullet,
+            BulletKind::ZigZag(ref mut bullet) => bullet,
+            BulletKind::Homing(ref mut bullet) => bullet,
         }
     }
 }
 
+impl Bullet for BulletKind {
+    fn update(&mut self, area: (f64, f64), targets: &[Target], dt: f64) -> bool {
+        self.as_bullet_mut().update(area, targets, dt)
+    }
+
+    fn render(&self, phi: &mut Phi) {
+        self.as_bullet().render(phi)
+    }
+
+    fn rect(&self) -> Rectangle {
+        self.as_bullet().rect()
+    }
 
+    fn impact(&self) -> &Impact {
+        self.as_bullet().impact()
+    }
 
-#[derive(Clone, Copy)]
-pub enum CannonType {
-    RectBullet,
-    SineBullet { amplitude: f64, angular_vel: f64 },
-    DivergentBullet { a: f64, b: f64 },
+    fn impact_mut(&mut self) -> &mut Impact {
+        self.as_bullet_mut().impact_mut()
+    }
 }
 
 
-pub fn spawn_bullets(cannon: CannonType,
-                     cannons_x: f64,
-                     cannon1_y: f64,
-                     cannon2_y: f64) -> Vec<Box<Bullet>>
-{
-    match cannon {
-        CannonType::RectBullet =>
-            vec![
-                Box::new(RectBullet {
-                    rect: Rectangle {
-                        x: cannons_x,
-                        y: cannon1_y,
-                        w: BULLET_W,
-                        h: BULLET_H,
+
+/// The kind of bullet fired by a weapon, along with its trajectory parameters.
+#[derive(Clone, Copy, Debug)]
+pub enum Projectile {
+    Rect,
+    Sine { amplitude: f64, angular_vel: f64 },
+    Divergent { a: f64, b: f64 },
+    /// `growth` is in pixels per second, `angular_vel` in radians per second.
+    Spiral { growth: f64, angular_vel: f64 },
+    /// `period` is the time, in seconds, taken by a full zig and zag.
+    ZigZag { amplitude: f64, period: f64 },
+    /// `turn_rate` is in degrees per second, `lifetime` in seconds.
+    Homing { turn_rate: f64, lifetime: f64 },
+}
+
+/// What a weapon fires.
+#[derive(Clone, Copy, Debug)]
+pub enum WeaponKind {
+    /// Bullets, `count` of them per shot.
+    Bullets(Projectile),
+    /// A continuous ray, `width` pixels wide, stopped by the first target it meets.
+    /// Its damage, heat and ammo are spent per second of firing rather than per shot.
+    Beam { width: f64 },
+}
+
+/// A resource limiting how long a weapon can keep firing.
+#[derive(Clone, Copy, Debug)]
+pub enum WeaponResource {
+    /// The weapon can fire forever, only limited by its fire rate.
+    Unlimited,
+    /// Every shot adds heat. At `MAX_HEAT`, the weapon overheats and cannot
+    /// fire again until it has completely cooled down.
+    Heat { per_shot: f64, cooling: f64 },
+    /// Every shot uses one round. Rounds slowly come back.
+    Ammo { max: f64, recharge: f64 },
+}
+
+/// Heat at which a weapon overheats.
+pub const MAX_HEAT: f64 = 100.0;
+
+/// A weapon, as described in the weapons file.
+#[derive(Clone, Debug)]
+pub struct WeaponDef {
+    pub name: String,
+    pub kind: WeaponKind,
+    /// Bullets fired per shot, spread evenly between the cannons.
+    pub count: usize,
+    /// Angle, in degrees, between the outermost bullets.
+    pub spread: f64,
+    /// Pixels traveled by a bullet every second.
+    pub speed: f64,
+    /// Damage dealt by every bullet, or by a beam every second.
+    pub damage: f64,
+    /// Additional targets every bullet can go through.
+    pub pierce: usize,
+    /// Times a bullet can bounce off the edges of the screen, `Rect` bullets only.
+    pub bounces: usize,
+    /// Shots per second.
+    pub fire_rate: f64,
+    pub resource: WeaponResource,
+}
+
+impl WeaponDef {
+    /// Load every weapon of a weapons file, in order.
+    /// Invalid weapons are reported and skipped.
+    pub fn load_all<P: AsRef<Path>>(path: P) -> io::Result<Vec<WeaponDef>> {
+        let sections = try!(config::load(path));
+
+        Ok(sections.iter()
+            .filter_map(|section| match WeaponDef::from_section(section) {
+                Ok(weapon) => Some(weapon),
+                Err(err) => {
+                    println!("Skipping weapon [{}]: {}", section.name, err);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    fn from_section(section: &Section) -> Result<WeaponDef, String> {
+        let param = |key: &str| section.parse::<f64>(key)
+            .ok_or(format!("missing or invalid `{}`", key));
+
+        let kind = if section.get("beam").is_some() {
+            let width = try!(param("beam"));
+            if width <= 0.0 {
+                return Err("`beam` must be positive".to_string());
+            }
+            WeaponKind::Beam { width: width }
+        } else {
+            WeaponKind::Bullets(match section.get("projectile") {
+                Some("rect") => Projectile::Rect,
+                Some("sine") => Projectile::Sine {
+                    amplitude: try!(param("amplitude")),
+                    angular_vel: try!(param("angular_vel")),
+                },
+                Some("divergent") => Projectile::Divergent {
+                    a: try!(param("a")),
+                    b: try!(param("b")),
+                },
+                Some("spiral") => Projectile::Spiral {
+                    growth: try!(param("growth")),
+                    angular_vel: try!(param("angular_vel")),
+                },
+                Some("zigzag") => {
+                    let period = try!(param("period"));
+                    if period <= 0.0 {
+                        return Err("`period` must be positive".to_string());
                     }
-                }),
-                Box::new(RectBullet {
+                    Projectile::ZigZag {
+                        amplitude: try!(param("amplitude")),
+                        period: period,
+                    }
+                },
+                Some("homing") => Projectile::Homing {
+                    turn_rate: try!(param("turn_rate")),
+                    lifetime: try!(param("lifetime")),
+                },
+                Some(other) => return Err(format!("unknown projectile `{}`", other)),
+                None => return Err("missing `projectile` or `beam`".to_string()),
+            })
+        };
+
+        let resource =
+            if let Some(per_shot) = section.parse("heat") {
+                WeaponResource::Heat {
+                    per_shot: per_shot,
+                    cooling: try!(param("cooling")),
+                }
+            } else if let Some(max) = section.parse("ammo") {
+                WeaponResource::Ammo {
+                    max: max,
+                    recharge: try!(param("reload")),
+                }
+            } else {
+                WeaponResource::Unlimited
+            };
+
+        if let WeaponResource::Ammo { max, .. } = resource {
+            if max < 1.0 {
+                return Err("`ammo` must be at least 1".to_string());
+            }
+        }
+
+        let count = section.parse_or("count", 2);
+        if count == 0 {
+            return Err("`count` must be at least 1".to_string());
+        }
+
+        let fire_rate = section.parse_or("fire_rate", 4.0);
+        if fire_rate <= 0.0 {
+            return Err("`fire_rate` must be positive".to_string());
+        }
+
+        Ok(WeaponDef {
+            name: section.name.clone(),
+            kind: kind,
+            count: count,
+            spread: section.parse_or("spread", 0.0),
+            speed: section.parse_or("speed", BULLET_SPEED),
+            damage: section.parse_or("damage", 1.0),
+            pierce: section.parse_or("pierce", 0),
+            bounces: section.parse_or("bounces", 0),
+            fire_rate: fire_rate,
+            resource: resource,
+        })
+    }
+
+    /// Make the weapon stronger as it reaches the upgrade `level`, starting from 1.
+    /// Odd levels add a barrel, or widen a beam, and even levels make it fire faster,
+    /// or a beam hit harder.
+    pub fn level_up(&mut self, level: usize) {
+        match self.kind {
+            WeaponKind::Bullets(_) if level % 2 == 1 => self.count += 1,
+            WeaponKind::Bullets(_) => self.fire_rate *= 1.25,
+            WeaponKind::Beam { ref mut width } if level % 2 == 1 => *width *= 1.5,
+            WeaponKind::Beam { .. } => self.damage *= 1.25,
+        }
+    }
+}
+
+
+/// What a weapon's resource meter should display.
+pub struct Meter {
+    /// From 0.0 (empty or cold) to 1.0 (full or overheated).
+    pub fill: f64,
+    /// Whether the weapon is unable to fire until the meter recovers.
+    pub blocked: bool,
+}
+
+/// The state of a weapon between shots: its cooldown and its heat or ammo.
+pub struct WeaponState {
+    /// Time before the next shot is allowed.
+    cooldown: f64,
+    heat: f64,
+    overheated: bool,
+    ammo: f64,
+}
+
+impl WeaponState {
+    pub fn new(weapon: &WeaponDef) -> WeaponState {
+        WeaponState {
+            cooldown: 0.0,
+            heat: 0.0,
+            overheated: false,
+            ammo: match weapon.resource {
+                WeaponResource::Ammo { max, .. } => max,
+                _ => 0.0,
+            },
+        }
+    }
+
+    /// Adds the cooldown, heat and ammo, which decide when the weapon fires next.
+    pub fn add_to(&self, checksum: &mut Checksum) {
+        checksum.add_f64(self.cooldown);
+        checksum.add_f64(self.heat);
+        checksum.add_usize(self.overheated as usize);
+        checksum.add_f64(self.ammo);
+    }
+
+    /// Let the weapon cool down and reload.
+    pub fn update(&mut self, weapon: &WeaponDef, dt: f64) {
+        self.cooldown = (self.cooldown - dt).max(0.0);
+
+        match weapon.resource {
+            WeaponResource::Unlimited => {},
+            WeaponResource::Heat { cooling, .. } => {
+                self.heat = (self.heat - cooling * dt).max(0.0);
+                if self.heat == 0.0 {
+                    self.overheated = false;
+                }
+            },
+            WeaponResource::Ammo { max, recharge } => {
+                self.ammo = (self.ammo + recharge * dt).min(max);
+            },
+        }
+    }
+
+    /// If the weapon is ready, consume one shot and return `true`.
+    pub fn try_fire(&mut self, weapon: &WeaponDef) -> bool {
+        if self.cooldown > 0.0 {
+            return false;
+        }
+
+        match weapon.resource {
+            WeaponResource::Unlimited => {},
+            WeaponResource::Heat { per_shot, .. } => {
+                if self.overheated {
+                    return false;
+                }
+                self.heat += per_shot;
+                if self.heat >= MAX_HEAT {
+                    self.heat = MAX_HEAT;
+                    self.overheated = true;
+                }
+            },
+            WeaponResource::Ammo { .. } => {
+                if self.ammo < 1.0 {
+                    return false;
+                }
+                self.ammo -= 1.0;
+            },
+        }
+
+        self.cooldown = 1.0 / weapon.fire_rate;
+        true
+    }
+
+    /// Keep a beam firing for `dt` seconds, spending its heat or ammo per second.
+    /// Return `false` if the weapon has overheated or run dry.
+    pub fn try_sustain(&mut self, weapon: &WeaponDef, dt: f64) -> bool {
+        match weapon.resource {
+            WeaponResource::Unlimited => {},
+            WeaponResource::Heat { per_shot, .. } => {
+                if self.overheated {
+                    return false;
+                }
+                self.heat += per_shot * dt;
+                if self.heat >= MAX_HEAT {
+                    self.heat = MAX_HEAT;
+                    self.overheated = true;
+                }
+            },
+            WeaponResource::Ammo { .. } => {
+                if self.ammo <= 0.0 {
+                    return false;
+                }
+                self.ammo = (self.ammo - dt).max(0.0);
+            },
+        }
+
+        true
+    }
+
+    /// The meter to display for this weapon, if it has a limited resource.
+    pub fn meter(&self, weapon: &WeaponDef) -> Option<Meter> {
+        match weapon.resource {
+            WeaponResource::Unlimited => None,
+            WeaponResource::Heat { .. } => Some(Meter {
+                fill: self.heat / MAX_HEAT,
+                blocked: self.overheated,
+            }),
+            WeaponResource::Ammo { max, .. } => Some(Meter {
+                fill: self.ammo / max,
+                blocked: self.ammo < 1.0,
+            }),
+        }
+    }
+}
+
+
+fn path_bullet<T: Trajectory>(origin: (f64, f64), path: T, impact: Impact) -> PathBullet<T> {
+    PathBullet {
+        origin: origin,
+        path: path,
+        total_time: 0.0,
+        impact: impact,
+    }
+}
+
+/// Spawn a bullet centered on `origin` flying in the direction `angle`, in radians.
+pub fn fired_bullet(team: Team,
+                    origin: (f64, f64),
+                    angle: f64,
+                    speed: f64,
+                    damage: f64) -> BulletKind
+{
+    let vel = (speed * angle.cos(), speed * angle.sin());
+
+    BulletKind::Straight(path_bullet((origin.0 - BULLET_W / 2.0, origin.1 - BULLET_H / 2.0),
+                                     Linear { vel: vel },
+                                     Impact::new(team, damage, 0)))
+}
+
+/// Spawn one shot of `weapon` towards `heading`, in radians, 0 pointing right, handing
+/// every bullet to `fire`.
+/// Its bullets are spread evenly from `cannon1` to `cannon2`, given as the top-left corner
+/// of the bullets. Patterns are laid out as if the bullets were fired to the right with
+/// `cannon1` above, and rotated towards `heading`.
+pub fn spawn_bullets<F>(weapon: &WeaponDef,
+                        cannon1: (f64, f64),
+                        cannon2: (f64, f64),
+                        heading: f64,
+                        mut fire: F)
+    where F: FnMut(BulletKind)
+{
+    let projectile = match weapon.kind {
+        WeaponKind::Bullets(projectile) => projectile,
+        WeaponKind::Beam { .. } => return,
+    };
+
+    for i in 0..weapon.count {
+        // Goes from 0.0 for the bullet of the first cannon to 1.0 for the second one.
+        let t = if weapon.count == 1 { 0.5 }
+                else { i as f64 / (weapon.count - 1) as f64 };
+
+        let origin = (cannon1.0 + (cannon2.0 - cannon1.0) * t,
+                      cannon1.1 + (cannon2.1 - cannon1.1) * t);
+        let angle = heading + (weapon.spread * (t - 0.5)).to_radians();
+        let vel = (weapon.speed * angle.cos(), weapon.speed * angle.sin());
+
+        // Bullets in the upper half go up, those in the lower half go down.
+        let side = if t < 0.5 { -1.0 } else if t > 0.5 { 1.0 } else { 0.0 };
+        let mirror = if t < 0.5 { -1.0 } else { 1.0 };
+
+        // Every trajectory is followed on top of the bullet's straight flight.
+        let base = Linear { vel: vel };
+
+        let impact = Impact::new(Team::Player, weapon.damage, weapon.pierce);
+
+        fire(match projectile {
+            Projectile::Rect =>
+                BulletKind::Rect(RectBullet {
                     rect: Rectangle {
-                        x: cannons_x,
-                        y: cannon2_y,
+                        x: origin.0,
+                        y: origin.1,
                         w: BULLET_W,
                         h: BULLET_H,
-                    }
+                    },
+                    vel: vel,
+                    bounces: weapon.bounces,
+                    impact: impact,
                 }),
-            ],
 
-        CannonType::SineBullet { amplitude, angular_vel } =>
-            vec![
-                Box::new(SineBullet {
-                    pos_x: cannons_x,
-                    origin_y: cannon1_y,
-                    amplitude: amplitude,
-                    angular_vel: angular_vel,
-                    total_time: 0.0,
-                }),
-                Box::new(SineBullet {
-                    pos_x: cannons_x,
-                    origin_y: cannon2_y,
+            Projectile::Sine { amplitude, angular_vel } =>
+                BulletKind::Sine(path_bullet(origin, base.add(trajectory::Sine {
                     amplitude: amplitude,
                     angular_vel: angular_vel,
-                    total_time: 0.0,
-                }),
-            ],
-
-        CannonType::DivergentBullet { a, b } =>
-            vec![
-                // If a,b > 0, eventually goes upwards
-                Box::new(DivergentBullet {
-                    pos_x: cannons_x,
-                    origin_y: cannon1_y,
-                    a: -a,
-                    b: b,
-                    total_time: 0.0,
-                }),
-                // If a,b > 0, eventually goes downwards
-                Box::new(DivergentBullet {
-                    pos_x: cannons_x,
-                    origin_y: cannon2_y,
-                    a: a,
+                }.rotate(heading)), impact)),
+
+            // If a,b > 0, the upper bullets eventually go upwards and the lower ones downwards
+            Projectile::Divergent { a, b } =>
+                BulletKind::Divergent(path_bullet(origin, base.add(trajectory::Divergent {
+                    a: a * side,
                     b: b,
-                    total_time: 0.0,
+                }.rotate(heading)), impact)),
+
+            // The upper bullets are mirrored, so that both cannons draw symmetric patterns
+            Projectile::Spiral { growth, angular_vel } =>
+                BulletKind::Spiral(path_bullet(origin, base.add(trajectory::Spiral {
+                    growth: growth,
+                    angular_vel: angular_vel,
+                }.scale(1.0, mirror).rotate(heading)), impact)),
+
+            Projectile::ZigZag { amplitude, period } =>
+                BulletKind::ZigZag(path_bullet(origin, base.add(trajectory::ZigZag {
+                    amplitude: amplitude,
+                    period: period,
+                }.scale(1.0, mirror).rotate(heading)), impact)),
+
+            Projectile::Homing { turn_rate, lifetime } =>
+                BulletKind::Homing(HomingBullet {
+                    center: (origin.0 + BULLET_W / 2.0, origin.1 + BULLET_H / 2.0),
+                    heading: angle,
+                    speed: weapon.speed,
+                    turn_rate: turn_rate.to_radians(),
+                    launch: (heading.cos(), heading.sin()),
+                    lifetime: lifetime,
+                    target: None,
+                    impact: impact,
                 }),
-            ]
+        });
+    }
+}
+
+
+/// A beam, as cast during one frame.
+#[derive(Clone, Copy, Debug)]
+pub struct Beam {
+    pub origin: (f64, f64),
+    /// In radians, 0 pointing right.
+    pub angle: f64,
+    /// Where the beam stops, either on a target or at the edge of the screen.
+    pub end: (f64, f64),
+    pub width: f64,
+    /// The target which stopped the beam, if any.
+    pub hit: Option<usize>,
+}
+
+impl Beam {
+    pub fn length(&self) -> f64 {
+        ((self.end.0 - self.origin.0).powi(2) + (self.end.1 - self.origin.1).powi(2)).sqrt()
     }
-}
\ No newline at end of file
+
+    /// Draw the beam by stretching `sprite` from its origin to its end. Nothing is drawn
+    /// for beams shorter than a pixel, e.g. when the cannons are inside a target.
+    pub fn render<T: Renderable>(&self, phi: &mut Phi, sprite: &T) {
+        let length = self.length();
+        if length < 1.0 {
+            return;
+        }
+
+        let params = DrawParams::new()
+            .rotated(self.angle.to_degrees())
+            .around((0.0, self.width / 2.0))
+            .with_blend(BlendMode::Add);
+
+        phi.queue.sprite_ex(Layer::Entities, BULLET_Z, sprite, Rectangle {
+            x: self.origin.0,
+            y: self.origin.1 - self.width / 2.0,
+            w: length,
+            h: self.width,
+        }, params);
+    }
+}
+
+/// What pressing the trigger for one frame produced.
+pub enum Shot {
+    /// Bullets, already handed over to be spawned.
+    Bullets,
+    Beam(Beam),
+}
+
+/// Cast `weapon`'s beam from `origin` towards `angle`, in radians, up to the first of
+/// `targets` on its way or to the edge of `area`, whichever comes first.
+pub fn cast_beam(weapon: &WeaponDef,
+                 origin: (f64, f64),
+                 angle: f64,
+                 targets: &[Target],
+                 area: (f64, f64)) -> Beam
+{
+    let width = match weapon.kind {
+        WeaponKind::Beam { width } => width,
+        WeaponKind::Bullets(_) => 0.0,
+    };
+
+    // Distance to the edge the beam is heading to, along either axis.
+    let direction = (angle.cos(), angle.sin());
+    let to_edge = |origin: f64, dir: f64, size: f64| {
+        if dir > 0.0 { (size - origin) / dir }
+        else if dir < 0.0 { -origin / dir }
+        else { ::std::f64::INFINITY }
+    };
+
+    let range = to_edge(origin.0, direction.0, area.0)
+        .min(to_edge(origin.1, direction.1, area.1))
+        .max(0.0);
+    let mut nearest = (range, None);
+
+    for target in targets {
+        if let Some(distance) = target.rect.raycast(origin, direction) {
+            if distance < nearest.0 {
+                nearest = (distance, Some(target.id));
+            }
+        }
+    }
+
+    Beam {
+        origin: origin,
+        angle: angle,
+        end: (origin.0 + direction.0 * nearest.0, origin.1 + direction.1 * nearest.0),
+        width: width,
+        hit: nearest.1,
+    }
+}
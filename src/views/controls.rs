@@ -0,0 +1,98 @@
+use phi::Events;
+
+
+/// What a player asks of their ship during a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Controls {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub fire: bool,
+    /// Weapon picked this frame, if any.
+    pub weapon: Option<usize>,
+    /// Whether the player asked for the next weapon this frame.
+    pub next_weapon: bool,
+}
+
+impl Controls {
+    /// Packs the controls into 16 bits, e.g. to send them over the network: one bit per
+    /// key, then the picked weapon plus one, zero meaning none.
+    pub fn to_bits(&self) -> u16 {
+        let keys = [self.up, self.down, self.left, self.right, self.fire, self.next_weapon];
+        let bits = keys.iter().enumerate()
+            .fold(0, |bits, (i, &key)| if key { bits | 1 << i } else { bits });
+
+        bits | self.weapon.map_or(0, |weapon| weapon as u16 + 1) << keys.len()
+    }
+
+    /// Unpacks controls packed by `to_bits`.
+    pub fn from_bits(bits: u16) -> Controls {
+        let key = |i: u16| bits & 1 << i != 0;
+        let weapon = bits >> 6;
+
+        Controls {
+            up: key(0),
+            down: key(1),
+            left: key(2),
+            right: key(3),
+            fire: key(4),
+            weapon: if weapon == 0 { None } else { Some(weapon as usize - 1) },
+            next_weapon: key(5),
+        }
+    }
+}
+
+
+/// The keys a player flies their ship with. Players share the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bindings {
+    /// Arrows to move, space to fire and the number keys to pick a weapon.
+    Arrows,
+    /// WASD to move, left shift to fire and Q for the next weapon.
+    Wasd,
+}
+
+impl Bindings {
+    /// Bindings of the `index`th player. There are only two sets of keys, further players
+    /// share the second one.
+    pub fn for_player(index: usize) -> Bindings {
+        match index {
+            0 => Bindings::Arrows,
+            _ => Bindings::Wasd,
+        }
+    }
+
+    pub fn read(self, events: &Events) -> Controls {
+        match self {
+            Bindings::Arrows => {
+                let weapon_keys = [
+                    events.now.key_1, events.now.key_2, events.now.key_3,
+                    events.now.key_4, events.now.key_5, events.now.key_6,
+                    events.now.key_7, events.now.key_8, events.now.key_9,
+                ];
+
+                Controls {
+                    up: events.key_up,
+                    down: events.key_down,
+                    left: events.key_left,
+                    right: events.key_right,
+                    fire: events.key_space,
+                    // The last key pressed wins
+                    weapon: weapon_keys.iter().rposition(|&key| key == Some(true)),
+                    next_weapon: false,
+                }
+            },
+
+            Bindings::Wasd => Controls {
+                up: events.key_w,
+                down: events.key_s,
+                left: events.key_a,
+                right: events.key_d,
+                fire: events.key_lshift,
+                weapon: None,
+                next_weapon: events.now.key_q == Some(true),
+            },
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use phi::config::{self, Section};
+use phi::net::Checksum;
+use views::enemies::{EnemyKind, EnemyWave};
+use views::level::{AsteroidSize, Event};
+use std::io;
+use std::path::Path;
+
+
+/// A parameter which goes from `start`, at difficulty 0, to `end`, at difficulty 1.
+#[derive(Clone, Copy, Debug)]
+pub struct Curve {
+    pub start: f64,
+    pub end: f64,
+    /// Bends the curve: 1 is a straight line, above 1 the parameter changes slowly at first
+    /// then faster, below 1 the other way round.
+    pub shape: f64,
+}
+
+impl Curve {
+    pub fn at(&self, difficulty: f64) -> f64 {
+        let progress = difficulty.max(0.0).min(1.0).powf(self.shape);
+        self.start + (self.end - self.start) * progress
+    }
+
+    fn from_section(section: &Section, default: Curve) -> Result<Curve, String> {
+        let curve = Curve {
+            start: section.parse_or("start", default.start),
+            end: section.parse_or("end", default.end),
+            shape: section.parse_or("shape", default.shape),
+        };
+
+        if curve.shape <= 0.0 {
+            return Err("`shape` must be positive".to_string());
+        }
+        Ok(curve)
+    }
+}
+
+
+/// How quickly endless mode gets harder, and what harder means.
+#[derive(Clone, Copy, Debug)]
+pub struct DifficultyCurve {
+    /// Seconds of play, and points scored, which each bring the difficulty to its maximum.
+    pub full_time: f64,
+    pub full_score: f64,
+    /// Difficulty taken away when the player loses a life, and regained every second.
+    pub setback: f64,
+    pub recovery: f64,
+    /// Seconds between boss fights, not counting the fights themselves. 0 means no bosses.
+    pub boss_interval: f64,
+
+    /// Asteroids spawned every second.
+    pub asteroid_rate: Curve,
+    /// Range of the asteroids' speeds, in pixels per second.
+    pub min_speed: Curve,
+    pub max_speed: Curve,
+    /// Share of the asteroids which are small and large, the others being medium.
+    pub small_share: Curve,
+    pub large_share: Curve,
+    /// Waves of enemies spawned every minute.
+    pub enemy_rate: Curve,
+}
+
+/// Used for whatever the difficulty file leaves out.
+pub const DEFAULT_CURVE: DifficultyCurve = DifficultyCurve {
+    full_time: 300.0,
+    full_score: 5000.0,
+    setback: 0.15,
+    recovery: 0.01,
+    boss_interval: 60.0,
+    asteroid_rate: Curve { start: 2.0, end: 6.0, shape: 1.0 },
+    min_speed: Curve { start: 50.0, end: 120.0, shape: 1.0 },
+    max_speed: Curve { start: 150.0, end: 320.0, shape: 1.5 },
+    small_share: Curve { start: 0.0, end: 0.4, shape: 1.0 },
+    large_share: Curve { start: 1.0, end: 0.3, shape: 0.5 },
+    enemy_rate: Curve { start: 1.0, end: 6.0, shape: 1.0 },
+};
+
+impl DifficultyCurve {
+    /// Loads a difficulty file. Invalid entries are skipped with a warning.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<DifficultyCurve> {
+        let sections = try!(config::load(path));
+        let mut tuned = DEFAULT_CURVE;
+
+        for section in &sections {
+            let curve = match &section.name[..] {
+                "" => {
+                    tuned.full_time = section.parse_or("time", tuned.full_time);
+                    tuned.full_score = section.parse_or("score", tuned.full_score);
+                    tuned.setback = section.parse_or("setback", tuned.setback);
+                    tuned.recovery = section.parse_or("recovery", tuned.recovery);
+                    tuned.boss_interval = section.parse_or("boss_interval", tuned.boss_interval);
+                    continue;
+                },
+                "asteroid_rate" => &mut tuned.asteroid_rate,
+                "min_speed" => &mut tuned.min_speed,
+                "max_speed" => &mut tuned.max_speed,
+                "small_share" => &mut tuned.small_share,
+                "large_share" => &mut tuned.large_share,
+                "enemy_rate" => &mut tuned.enemy_rate,
+                other => {
+                    println!("Skipping curve [{}]: unknown parameter", other);
+                    continue;
+                },
+            };
+
+            match Curve::from_section(section, *curve) {
+                Ok(tuned_curve) => *curve = tuned_curve,
+                Err(err) => println!("Skipping curve [{}]: {}", section.name, err),
+            }
+        }
+
+        if tuned.full_time <= 0.0 || tuned.full_score <= 0.0 {
+            println!("Difficulty `time` and `score` must be positive, using the defaults");
+            tuned.full_time = DEFAULT_CURVE.full_time;
+            tuned.full_score = DEFAULT_CURVE.full_score;
+        }
+
+        Ok(tuned)
+    }
+}
+
+
+/// The parameters of endless mode at some difficulty.
+#[derive(Clone, Copy, Debug)]
+pub struct Parameters {
+    pub difficulty: f64,
+    pub asteroid_rate: f64,
+    pub speed: (f64, f64),
+    /// Shares of small, medium and large asteroids, adding up to 1.
+    pub sizes: (f64, f64, f64),
+    pub enemy_rate: f64,
+}
+
+
+/// Spawns asteroids and enemies for endless mode, more and faster as time and score go up,
+/// and a boss every now and then.
+pub struct Difficulty {
+    curve: DifficultyCurve,
+    time: f64,
+    score: usize,
+    /// Difficulty taken away after the player lost lives, and not regained yet.
+    penalty: f64,
+    /// Fractions of asteroids and waves owed, spawned once they add up to a whole one.
+    asteroids_due: f64,
+    waves_due: f64,
+    /// Seconds left before the next boss.
+    boss_timer: f64,
+}
+
+impl Difficulty {
+    pub fn new(curve: DifficultyCurve) -> Difficulty {
+        Difficulty {
+            curve: curve,
+            time: 0.0,
+            score: 0,
+            penalty: 0.0,
+            asteroids_due: 0.0,
+            waves_due: 0.0,
+            boss_timer: curve.boss_interval,
+        }
+    }
+
+    /// Difficulty earned through time and score alone.
+    fn earned(&self) -> f64 {
+        (self.time / self.curve.full_time + self.score as f64 / self.curve.full_score).min(1.0)
+    }
+
+    pub fn parameters(&self) -> Parameters {
+        let curve = &self.curve;
+        let difficulty = (self.earned() - self.penalty).max(0.0);
+
+        let min_speed = curve.min_speed.at(difficulty);
+        let max_speed = curve.max_speed.at(difficulty).max(min_speed);
+
+        let small = curve.small_share.at(difficulty).max(0.0);
+        let large = curve.large_share.at(difficulty).max(0.0);
+        // Too many small and large asteroids leave no room for medium ones.
+        let (small, large) = if small + large > 1.0 {
+            (small / (small + large), large / (small + large))
+        } else {
+            (small, large)
+        };
+
+        Parameters {
+            difficulty: difficulty,
+            asteroid_rate: curve.asteroid_rate.at(difficulty).max(0.0),
+            speed: (min_speed, max_speed),
+            sizes: (small, 1.0 - small - large, large),
+            enemy_rate: curve.enemy_rate.at(difficulty).max(0.0),
+        }
+    }
+
+    /// Eases off after the player lost a life. It never goes below the difficulty of a
+    /// fresh start, and is regained over time.
+    pub fn set_back(&mut self) {
+        self.penalty = (self.penalty + self.curve.setback).min(self.earned());
+    }
+
+    /// Adds how far the game went and how much is due to spawn.
+    pub fn add_to(&self, checksum: &mut Checksum) {
+        checksum.add_f64(self.time);
+        checksum.add_usize(self.score);
+        checksum.add_f64(self.penalty);
+        checksum.add_f64(self.asteroids_due);
+        checksum.add_f64(self.waves_due);
+        checksum.add_f64(self.boss_timer);
+    }
+
+    /// Advance by `dt` seconds, returning what the game should spawn.
+    /// Nothing spawns while `boss` is being fought, and the next one is due a full interval
+    /// after it is gone.
+    pub fn update(&mut self, score: usize, boss: bool, dt: f64) -> Vec<Event> {
+        self.time += dt;
+        self.score = score;
+        self.penalty = (self.penalty - self.curve.recovery * dt).max(0.0);
+
+        if boss {
+            self.boss_timer = self.curve.boss_interval;
+            return vec![];
+        }
+
+        let params = self.parameters();
+        let mut events = vec![];
+
+        if self.curve.boss_interval > 0.0 {
+            self.boss_timer -= dt;
+            if self.boss_timer <= 0.0 {
+                self.boss_timer = self.curve.boss_interval;
+                events.push(Event::Boss);
+            }
+        }
+
+        self.asteroids_due += params.asteroid_rate * dt;
+        while self.asteroids_due >= 1.0 {
+            self.asteroids_due -= 1.0;
+
+            let (small, medium, _) = params.sizes;
+            let pick = ::phi::rng::random::<f64>();
+            let size =
+                if pick < small { AsteroidSize::Small }
+                else if pick < small + medium { AsteroidSize::Medium }
+                else { AsteroidSize::Large };
+
+            let (min_speed, max_speed) = params.speed;
+            events.push(Event::Asteroid {
+                size: size,
+                y: None,
+                speed: Some(min_speed + ::phi::rng::random::<f64>() * (max_speed - min_speed)),
+            });
+        }
+
+        self.waves_due += params.enemy_rate / 60.0 * dt;
+        while self.waves_due >= 1.0 {
+            self.waves_due -= 1.0;
+
+            let kinds = EnemyKind::all();
+            let kind = kinds[::phi::rng::random::<usize>() % kinds.len()];
+            events.push(Event::Enemies(EnemyWave::new(kind)));
+        }
+
+        events
+    }
+}
@@ -0,0 +1,465 @@
+use phi::Phi;
+use phi::data::Rectangle;
+use phi::gfx::{DrawParams, Layer, Sprite};
+use phi::net::Checksum;
+use sdl2::pixels::Color;
+use views::bullets::{BulletKind, Target, Team};
+use views::patterns::{self, Pattern, PatternEmitter};
+
+
+pub const ENEMY_W: f64 = 43.0;
+pub const ENEMY_H: f64 = 39.0;
+/// Enemies are drawn along with the bullets, between the player and asteroids.
+pub const ENEMY_Z: i32 = 1;
+
+/// Time before an enemy fires its first shot, so that it does not shoot while entering the screen.
+const FIRST_SHOT_DELAY: f64 = 1.0;
+
+/// Time spent chasing the player before giving up and flying away.
+const SEEK_DURATION: f64 = 6.0;
+/// Distance under which fleeing enemies start to steer away from the player.
+const FLEE_RADIUS: f64 = 200.0;
+/// Distance from its destination at which an enemy starts to slow down.
+const ARRIVAL_RADIUS: f64 = 80.0;
+/// Pixels kept between the screen's edges and strafing or hovering enemies.
+const EDGE_MARGIN: f64 = 40.0;
+/// Speed multiplier of a kamikaze while diving.
+const DIVE_BOOST: f64 = 2.5;
+
+
+/// How an enemy moves, along with the state of its current maneuver.
+#[derive(Clone, Copy, Debug)]
+pub enum Behaviour {
+    /// Chase the player for a while, then fly away to the left.
+    Seek,
+    /// Cross the screen from right to left, steering away from the player when it gets close.
+    Flee,
+    /// Keep its place, `offset` away from a leader point which crosses the screen.
+    /// Enemies spawned with the same leader fly along in formation.
+    Formation { leader: (f64, f64), leader_vel: (f64, f64), offset: (f64, f64) },
+    /// Fly to the column `x`, sweep up and down it for `duration` seconds while firing,
+    /// then retreat to the right.
+    Strafe { x: f64, duration: f64 },
+    /// Hover in front of the player for `delay` seconds, then dive in a straight line
+    /// towards where it was.
+    Kamikaze { delay: f64, dive: Option<(f64, f64)> },
+}
+
+/// The kinds of enemies which can be spawned, each with its own stats and behaviour.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnemyKind {
+    Chaser,
+    Drifter,
+    Wingman,
+    Strafer,
+    Kamikaze,
+}
+
+/// Stats shared by every enemy of a kind.
+#[derive(Clone, Copy, Debug)]
+pub struct EnemyDef {
+    pub hp: f64,
+    /// Maximum speed, in pixels per second.
+    pub speed: f64,
+    /// Maximum change of velocity, in pixels per second squared.
+    pub agility: f64,
+    /// What the enemy fires at the player, None for enemies which never shoot.
+    pub pattern: Option<Pattern>,
+    pub tint: Color,
+}
+
+impl EnemyKind {
+    pub fn all() -> [EnemyKind; 5] {
+        [EnemyKind::Chaser, EnemyKind::Drifter, EnemyKind::Wingman,
+         EnemyKind::Strafer, EnemyKind::Kamikaze]
+    }
+
+    /// The kind called `name` in level scripts, e.g. `wingman`.
+    pub fn from_name(name: &str) -> Option<EnemyKind> {
+        match name {
+            "chaser" => Some(EnemyKind::Chaser),
+            "drifter" => Some(EnemyKind::Drifter),
+            "wingman" => Some(EnemyKind::Wingman),
+            "strafer" => Some(EnemyKind::Strafer),
+            "kamikaze" => Some(EnemyKind::Kamikaze),
+            _ => None,
+        }
+    }
+
+    pub fn def(self) -> EnemyDef {
+        match self {
+            EnemyKind::Chaser => EnemyDef {
+                hp: 2.0, speed: 110.0, agility: 160.0, pattern: Some(patterns::AIMED_SHOT.with_rate(0.6)),
+                tint: Color::RGB(255, 120, 120),
+            },
+            EnemyKind::Drifter => EnemyDef {
+                hp: 1.0, speed: 90.0, agility: 200.0, pattern: Some(patterns::AIMED_FAN.with_rate(0.5)),
+                tint: Color::RGB(140, 255, 140),
+            },
+            EnemyKind::Wingman => EnemyDef {
+                hp: 1.0, speed: 160.0, agility: 400.0, pattern: Some(patterns::AIMED_SHOT.with_rate(0.4)),
+                tint: Color::RGB(140, 170, 255),
+            },
+            EnemyKind::Strafer => EnemyDef {
+                hp: 3.0, speed: 140.0, agility: 300.0, pattern: Some(patterns::ROTATING_STREAM.with_rate(6.0)),
+                tint: Color::RGB(255, 220, 100),
+            },
+            EnemyKind::Kamikaze => EnemyDef {
+                hp: 1.0, speed: 150.0, agility: 500.0, pattern: None,
+                tint: Color::RGB(255, 80, 40),
+            },
+        }
+    }
+}
+
+
+/// Shapes in which a group of enemies can fly, following a leader point across the screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Formation {
+    /// The leader in front, the others trailing behind, above and below.
+    V,
+    /// Side by side, one above the other.
+    Line,
+    /// One behind the other.
+    Column,
+}
+
+impl Formation {
+    /// The formation called `name` in level scripts, e.g. `v`.
+    pub fn from_name(name: &str) -> Option<Formation> {
+        match name {
+            "v" => Some(Formation::V),
+            "line" => Some(Formation::Line),
+            "column" => Some(Formation::Column),
+            _ => None,
+        }
+    }
+
+    /// Offset of the `i`th of `count` enemies from the formation's leader point.
+    fn offset(self, i: usize, count: usize) -> (f64, f64) {
+        match self {
+            Formation::V => {
+                // 0 leads, then pairs trail further behind, above and below.
+                let rank = ((i + 1) / 2) as f64;
+                let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+                (rank * ENEMY_W * 1.2, side * rank * ENEMY_H * 1.2)
+            },
+            Formation::Line =>
+                (0.0, (i as f64 - (count - 1) as f64 / 2.0) * ENEMY_H * 1.4),
+            Formation::Column =>
+                (i as f64 * ENEMY_W * 1.4, 0.0),
+        }
+    }
+}
+
+
+/// A group of enemies entering the screen together, as described by level scripts.
+#[derive(Clone, Copy, Debug)]
+pub struct EnemyWave {
+    pub kind: EnemyKind,
+    /// Fly in this formation rather than following the kind's own behaviour.
+    pub formation: Option<Formation>,
+    pub count: usize,
+    /// Height around which the wave enters, as a fraction of the screen's height.
+    /// None for a random height.
+    pub y: Option<f64>,
+    /// Maximum speed of the enemies, in pixels per second.
+    pub speed: f64,
+    pub pattern: Option<Pattern>,
+}
+
+impl EnemyWave {
+    /// A single enemy of `kind`, or the usual V of five for wingmen, with the kind's stats.
+    pub fn new(kind: EnemyKind) -> EnemyWave {
+        let def = kind.def();
+        let (formation, count) = match kind {
+            EnemyKind::Wingman => (Some(Formation::V), 5),
+            _ => (None, 1),
+        };
+
+        EnemyWave {
+            kind: kind,
+            formation: formation,
+            count: count,
+            y: None,
+            speed: def.speed,
+            pattern: def.pattern,
+        }
+    }
+}
+
+
+fn length(v: (f64, f64)) -> f64 {
+    (v.0 * v.0 + v.1 * v.1).sqrt()
+}
+
+/// `v` scaled to `len`, or zero if it has no direction.
+fn with_length(v: (f64, f64), len: f64) -> (f64, f64) {
+    let current = length(v);
+    if current == 0.0 {
+        (0.0, 0.0)
+    } else {
+        (v.0 * len / current, v.1 * len / current)
+    }
+}
+
+
+pub struct Enemy {
+    /// Unique for the whole game, shared with the asteroids' ids.
+    pub id: usize,
+    pub kind: EnemyKind,
+    pub hp: f64,
+    center: (f64, f64),
+    vel: (f64, f64),
+    /// Maximum speed, which level scripts may change from the kind's.
+    speed: f64,
+    behaviour: Behaviour,
+    sprite: Sprite,
+    /// Seconds since the enemy spawned.
+    time: f64,
+    emitter: Option<PatternEmitter>,
+}
+
+impl Enemy {
+    /// Velocity the enemy would like to have, according to its behaviour.
+    fn desired_velocity(&mut self, player: (f64, f64), area: (f64, f64), dt: f64) -> (f64, f64) {
+        let speed = self.speed;
+        let to = |from: (f64, f64), to: (f64, f64)| (to.0 - from.0, to.1 - from.1);
+
+        // Like seeking, but slowing down to a stop on the destination.
+        let arrive = |center: (f64, f64), target: (f64, f64)| {
+            let offset = to(center, target);
+            let distance = length(offset);
+            with_length(offset, speed * (distance / ARRIVAL_RADIUS).min(1.0))
+        };
+
+        match self.behaviour {
+            Behaviour::Seek =>
+                if self.time < SEEK_DURATION {
+                    with_length(to(self.center, player), speed)
+                } else {
+                    (-speed, 0.0)
+                },
+
+            Behaviour::Flee => {
+                let away = to(player, self.center);
+                let distance = length(away);
+                let cruise = (-speed * 0.6, 0.0);
+
+                if distance < FLEE_RADIUS {
+                    // The closer the player, the harder the enemy steers away.
+                    let urgency = 1.0 - distance / FLEE_RADIUS;
+                    let flee = with_length(away, speed * urgency);
+                    (cruise.0 + flee.0, cruise.1 + flee.1)
+                } else {
+                    cruise
+                }
+            },
+
+            Behaviour::Formation { ref mut leader, leader_vel, offset } => {
+                leader.0 += leader_vel.0 * dt;
+                leader.1 += leader_vel.1 * dt;
+                let slot = (leader.0 + offset.0, leader.1 + offset.1);
+
+                // Keep up with the leader, and catch up on the slot when lagging behind.
+                let correction = arrive(self.center, slot);
+                (leader_vel.0 + correction.0, leader_vel.1 + correction.1)
+            },
+
+            Behaviour::Strafe { x, duration } => {
+                if self.time > duration {
+                    (speed, 0.0)
+                } else if (self.center.0 - x).abs() > 5.0 {
+                    arrive(self.center, (x, self.center.1))
+                } else {
+                    // Sweep between the top and bottom of the screen.
+                    let span = area.1 - 2.0 * EDGE_MARGIN;
+                    let phase = (self.time * speed / span) % 2.0;
+                    let y = EDGE_MARGIN + span * if phase < 1.0 { phase } else { 2.0 - phase };
+                    arrive(self.center, (x, y))
+                }
+            },
+
+            Behaviour::Kamikaze { delay, ref mut dive } => {
+                if dive.is_none() && self.time >= delay {
+                    *dive = Some(with_length(to(self.center, player), 1.0));
+                }
+
+                match *dive {
+                    Some(direction) =>
+                        (direction.0 * speed * DIVE_BOOST, direction.1 * speed * DIVE_BOOST),
+                    None =>
+                        arrive(self.center, (area.0 - EDGE_MARGIN * 3.0, player.1)),
+                }
+            },
+        }
+    }
+
+    /// Steer towards the behaviour's desired velocity and move.
+    /// Return whether the enemy is still alive, i.e. `false` once it has left the screen.
+    pub fn update(&mut self, player: (f64, f64), area: (f64, f64), dt: f64) -> bool {
+        let def = self.kind.def();
+        self.time += dt;
+
+        let desired = self.desired_velocity(player, area, dt);
+
+        // Diving kamikazes are too fast to turn, others only change course progressively.
+        let max_speed = match self.behaviour {
+            Behaviour::Kamikaze { dive: Some(_), .. } => self.speed * DIVE_BOOST,
+            Behaviour::Formation { leader_vel, .. } => self.speed + length(leader_vel),
+            _ => self.speed,
+        };
+
+        let steering = (desired.0 - self.vel.0, desired.1 - self.vel.1);
+        let max_steering = def.agility * dt;
+        let steering = if length(steering) > max_steering {
+            with_length(steering, max_steering)
+        } else {
+            steering
+        };
+
+        self.vel = (self.vel.0 + steering.0, self.vel.1 + steering.1);
+        if length(self.vel) > max_speed {
+            self.vel = with_length(self.vel, max_speed);
+        }
+
+        self.center.0 += self.vel.0 * dt;
+        self.center.1 += self.vel.1 * dt;
+
+        // Enemies spawn just past the right edge, only remove them once they are well off screen.
+        let (w, h) = area;
+        let (x, y) = self.center;
+        !(x < -ENEMY_W || x > w + 2.0 * ENEMY_W ||
+          y < -2.0 * ENEMY_H || y > h + 2.0 * ENEMY_H)
+    }
+
+    /// Keep firing the enemy's pattern at the player, handing the bullets to `fire`.
+    /// Enemies hold their fire while off screen or once they are behind the player.
+    pub fn fire<F>(&mut self, player: (f64, f64), area: (f64, f64), dt: f64, fire: F)
+        where F: FnMut(BulletKind)
+    {
+        // Strafers only fire while sweeping
+        let ready = match self.behaviour {
+            Behaviour::Strafe { x, duration } =>
+                self.time <= duration && (self.center.0 - x).abs() <= 5.0,
+            _ => true,
+        };
+
+        if !ready || self.center.0 > area.0 || self.center.0 < player.0 {
+            return;
+        }
+
+        if let Some(ref mut emitter) = self.emitter {
+            emitter.update(self.center, player, Team::Enemy, dt, fire);
+        }
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        // The player's ship, facing left and painted in the kind's color.
+        let mut params = DrawParams::new()
+            .flipped(true, false)
+            .tinted(self.kind.def().tint);
+
+        // Diving kamikazes point at where they are heading.
+        if let Behaviour::Kamikaze { dive: Some(direction), .. } = self.behaviour {
+            params = params.rotated(direction.1.atan2(direction.0).to_degrees() - 180.0);
+        }
+
+        phi.queue.sprite_ex(Layer::Entities, ENEMY_Z, &self.sprite, self.rect(), params);
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        self.center
+    }
+
+    pub fn rect(&self) -> Rectangle {
+        Rectangle::with_size(ENEMY_W, ENEMY_H).center_at(self.center)
+    }
+
+    /// Describes the enemy to the bullets that may aim at it.
+    pub fn target(&self) -> Target {
+        Target {
+            id: self.id,
+            rect: self.rect(),
+        }
+    }
+
+    pub fn add_to(&self, checksum: &mut Checksum) {
+        checksum.add_usize(self.id);
+        checksum.add_f64(self.center.0);
+        checksum.add_f64(self.center.1);
+        checksum.add_f64(self.vel.0);
+        checksum.add_f64(self.vel.1);
+        checksum.add_f64(self.hp);
+        checksum.add_f64(self.time);
+
+        if let Some(ref emitter) = self.emitter {
+            emitter.add_to(checksum);
+        }
+    }
+}
+
+
+pub struct EnemyFactory {
+    sprite: Sprite,
+}
+
+impl EnemyFactory {
+    pub fn new(sprite: Sprite) -> EnemyFactory {
+        EnemyFactory {
+            sprite: sprite,
+        }
+    }
+
+    /// Spawn one of the wave's enemies with its own behaviour at `center`.
+    /// `ids` gives every enemy its unique id.
+    pub fn spawn<F>(&self, wave: &EnemyWave, center: (f64, f64), behaviour: Behaviour, ids: &mut F) -> Enemy
+        where F: FnMut() -> usize
+    {
+        Enemy {
+            id: ids(),
+            kind: wave.kind,
+            hp: wave.kind.def().hp,
+            center: center,
+            vel: (0.0, 0.0),
+            speed: wave.speed,
+            behaviour: behaviour,
+            sprite: self.sprite.clone(),
+            time: 0.0,
+            emitter: wave.pattern.map(|pattern|
+                PatternEmitter::new(pattern).delayed(FIRST_SHOT_DELAY)),
+        }
+    }
+
+    /// Spawn a wave of enemies entering the screen from the right.
+    /// Enemies which do not fly in formation come stacked one above the other.
+    pub fn wave<F>(&self, wave: &EnemyWave, area: (f64, f64), ids: &mut F) -> Vec<Enemy>
+        where F: FnMut() -> usize
+    {
+        let (w, h) = area;
+        let y = wave.y.unwrap_or_else(|| ::phi::rng::random::<f64>()) * h;
+        let y = y.max(EDGE_MARGIN).min(h - EDGE_MARGIN);
+        let entry = (w + ENEMY_W, y);
+
+        (0..wave.count).map(|i| {
+            if let Some(formation) = wave.formation {
+                let offset = formation.offset(i, wave.count);
+                let behaviour = Behaviour::Formation {
+                    leader: entry,
+                    leader_vel: (-wave.speed * 0.6, 0.0),
+                    offset: offset,
+                };
+                return self.spawn(wave, (entry.0 + offset.0, entry.1 + offset.1), behaviour, ids);
+            }
+
+            let behaviour = match wave.kind {
+                EnemyKind::Chaser => Behaviour::Seek,
+                EnemyKind::Drifter | EnemyKind::Wingman => Behaviour::Flee,
+                EnemyKind::Strafer => Behaviour::Strafe { x: w * 0.8, duration: 8.0 },
+                EnemyKind::Kamikaze => Behaviour::Kamikaze { delay: 1.5, dive: None },
+            };
+            let offset = Formation::Line.offset(i, wave.count);
+            self.spawn(wave, (entry.0 + ENEMY_W * i as f64, entry.1 + offset.1), behaviour, ids)
+        }).collect()
+    }
+}
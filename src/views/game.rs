@@ -1,12 +1,31 @@
 use ::phi::{Phi, View, ViewAction};
-use ::phi::data::{Rectangle, MaybeAlive};
-use ::phi::gfx::{CopySprite, Sprite, AnimatedSprite, AnimatedSpriteDescr};
+use ::phi::data::Rectangle;
+use ::phi::net::Checksum;
+use ::phi::pool::Pool;
+use ::phi::gfx::{DrawParams, EmitterDescr, Layer, ParticleEmitter, Sprite, AnimatedSprite, AnimatedSpriteDescr};
+use ::sdl2::render::BlendMode;
 use ::sdl2::pixels::Color;
 use ::views::shared::Background;
+use ::views::boss::Boss;
+use ::views::controls::{Bindings, Controls};
 use ::views::bullets::*;
+use ::views::enemies::{Enemy, EnemyFactory};
+use ::views::difficulty::{Difficulty, DifficultyCurve};
+use ::views::level::{AsteroidSize, Event, Level};
+use ::views::powerups::{self, PowerUp, PowerUpKind};
+use ::views::score::{self, Combo, ScorePopup};
+use ::std::collections::HashMap;
+use ::std::f64::consts::PI;
 
 // Constants
 const DEBUG: bool = false;
+// Where the game takes place. The logic never asks the window for its size, so that peers of
+// a network game play on the same field.
+const AREA: (f64, f64) = (::phi::LOGICAL_WIDTH as f64, ::phi::LOGICAL_HEIGHT as f64);
+
+// Draw order of the entities inside Layer::Entities
+const PLAYER_Z: i32 = 0;
+const ASTEROID_Z: i32 = 2;
 
 // Player Constants
 const PLAYER_SPEED: f64 = 180.0;
@@ -14,6 +33,43 @@ const PLAYER_W: f64 = 43.0;
 const PLAYER_H: f64 = 39.0;
 const PLAYER_PATH: &'static str =  "assets/spaceship.png";
 const PLAYER_MAX_LIVES: usize = 3;
+// Extra lives cannot bring the player above this many
+const PLAYER_LIVES_CAP: usize = 5;
+// Lives given back to a player revived by a teammate
+const REVIVE_LIVES: usize = 1;
+// Every player's ship is painted its own color, the first keeping the sprite's
+const PLAYER_TINTS: [Color; 4] = [
+	Color::RGB(255, 255, 255),
+	Color::RGB(140, 255, 160),
+	Color::RGB(255, 180, 100),
+	Color::RGB(160, 180, 255),
+];
+// Share of the screen's width in which the ships can move, from their side
+const PLAYER_REGION: f64 = 0.7;
+const VERSUS_REGION: f64 = 0.5;
+const PLAYER_FLASH_DURATION: f64 = 0.6;
+const PLAYER_FLASH_FPS: f64 = 10.0;
+
+// Weapon Constants
+const WEAPONS_PATH: &'static str = "assets/weapons.cfg";
+const MAX_WEAPON_LEVEL: usize = 4;
+const BEAM_PATH: &'static str = "assets/beam.png";
+const BEAM_FRAMES: usize = 4;
+const BEAM_FRAME_W: f64 = 32.0;
+const BEAM_FRAME_H: f64 = 16.0;
+const BEAM_FPS: f64 = 20.0;
+
+// HUD Constants
+const HUD_FONT: &'static str = "assets/belligerent.ttf";
+const HUD_FONT_SIZE: i32 = 20;
+const HUD_MARGIN: f64 = 10.0;
+const METER_W: f64 = 120.0;
+const METER_H: f64 = 10.0;
+const LIFE_ICON_SCALE: f64 = 0.6;
+// Seconds for which announcements, e.g. checkpoints, stay on screen
+const BANNER_DURATION: f64 = 2.5;
+const DEBUG_FONT_SIZE: i32 = 14;
+const POPUP_FONT_SIZE: i32 = 16;
 
 // Asteroid Constants
 const ASTEROID_PATH: &'static str = "assets/asteroid.png";
@@ -22,6 +78,7 @@ const ASTEROIDS_HIGH: usize = 7;
 const ASTEROIDS_TOTAL: usize = ASTEROIDS_WIDE * ASTEROIDS_HIGH - 4;
 const ASTEROID_SIDE: f64 = 96.0;
 const ASTEROID_AMOUNT: usize = 10;
+const ASTEROID_HP: f64 = 2.0;
 
 //Explosion Constants
 const EXPLOSION_PATH: &'static str = "assets/explosion.png";
@@ -32,13 +89,151 @@ const EXPLOSION_SIDE: f64 = 96.0;
 const EXPLOSION_FPS: f64 = 16.0;
 const EXPLOSION_DURATION: f64 = 1.0 / EXPLOSION_FPS * EXPLOSIONS_TOTAL as f64;
 
+// Sound Constants
+const SHOOT_SOUND: &'static str = "assets/sounds/shoot.wav";
+const EXPLOSION_SOUND: &'static str = "assets/sounds/explosion.wav";
+const HIT_SOUND: &'static str = "assets/sounds/hit.wav";
+const GAME_MUSIC: &'static str = "assets/sounds/music_game.wav";
+const PICKUP_SOUND: &'static str = "assets/sounds/menu_select.wav";
+
+// Particle Constants
+const EXHAUST: EmitterDescr = EmitterDescr {
+	rate: 90.0,
+	lifetime: (0.15, 0.35),
+	direction: 180.0,
+	spread: 25.0,
+	speed: (80.0, 140.0),
+	gravity: (0.0, 0.0),
+	drag: 2.0,
+	color_start: Color::RGBA(255, 220, 120, 255),
+	color_end: Color::RGBA(200, 40, 10, 0),
+	size_start: 5.0,
+	size_end: 2.0,
+	blend: BlendMode::Add,
+};
+
+const DEBRIS: EmitterDescr = EmitterDescr {
+	rate: 0.0,
+	lifetime: (0.5, 1.2),
+	direction: 0.0,
+	spread: 360.0,
+	speed: (40.0, 160.0),
+	gravity: (-60.0, 0.0),
+	drag: 1.0,
+	color_start: Color::RGBA(160, 140, 120, 255),
+	color_end: Color::RGBA(80, 70, 60, 0),
+	size_start: 4.0,
+	size_end: 2.0,
+	blend: BlendMode::Blend,
+};
+const DEBRIS_PER_ASTEROID: usize = 24;
+
+const SPARKS: EmitterDescr = EmitterDescr {
+	rate: 0.0,
+	lifetime: (0.1, 0.3),
+	direction: 180.0,
+	spread: 120.0,
+	speed: (100.0, 250.0),
+	gravity: (0.0, 0.0),
+	drag: 4.0,
+	color_start: Color::RGBA(255, 255, 180, 255),
+	color_end: Color::RGBA(255, 120, 20, 0),
+	size_start: 3.0,
+	size_end: 1.0,
+	blend: BlendMode::Add,
+};
+const SPARKS_PER_IMPACT: usize = 10;
+
+// Level Constants
+const LEVEL_PATH: &'static str = "assets/levels/1.cfg";
+// Seconds between the level's completion and the return to the main menu
+const LEVEL_END_DELAY: f64 = 4.0;
+const DIFFICULTY_PATH: &'static str = "assets/difficulty.cfg";
+// How the asteroids between the players of a versus game come in
+const VERSUS_PATH: &'static str = "assets/versus.cfg";
+
+// Score Constants
+// Points for destroying a large asteroid, smaller ones are worth less
+const ASTEROID_POINTS: usize = 10;
+const ENEMY_POINTS: usize = 25;
+const BOSS_POINTS: usize = 500;
+
+// Boss Constants
+const BOSS_NAME: &'static str = "Dreadnought";
+const BOSS_BAR_W: f64 = 400.0;
+const BOSS_BAR_H: f64 = 12.0;
+// Explosions set off around the boss when it is defeated
+const BOSS_EXPLOSIONS: usize = 6;
+const BOSS_DEFEAT_TRAUMA: f64 = 1.0;
+
+// Power-up Constants
+const SHIELD_DURATION: f64 = 10.0;
+// Damage dealt by a smart bomb to every part of the boss
+const SMART_BOMB_DAMAGE: f64 = 10.0;
+const SMART_BOMB_TRAUMA: f64 = 0.8;
+// Share of the drops which are revives, while a player is out of lives
+const REVIVE_CHANCE: f64 = 0.5;
+
+// Screen Shake Constants
+const HIT_TRAUMA: f64 = 0.6;
+const EXPLOSION_TRAUMA: f64 = 0.3;
+// Explosions further than this from the player do not shake the screen
+const EXPLOSION_SHAKE_RADIUS: f64 = 250.0;
+
 // The Player implementation
 struct Player {
+	// Position among the players, which decides the bindings, color and HUD column
+	index: usize,
+	// Players take the first ids, before any asteroid or enemy, so that rivals can aim at them
+	id: usize,
+	bindings: Bindings,
+	tint: Color,
+	side: Side,
 	rect: Rectangle,
+	// Where the ship appears, and reappears once revived
+	spawn: Rectangle,
+	// Share of the screen's width the ship can move in, from its side
+	region: f64,
 	sprites: Vec<Sprite>,
 	current: PlayerFrame,
-	cannon: CannonType,
+	// Weapons bound to the number keys, and the index of the one in use
+	weapons: Vec<WeaponDef>,
+	weapon_states: Vec<WeaponState>,
+	weapon_labels: Vec<Sprite>,
+	weapon: usize,
+	// Upgrades collected for every weapon
+	weapon_levels: Vec<usize>,
 	lives: usize,
+	// Time left for the ship to flash after being hit
+	flash_time: f64,
+	// Time left before the shield runs out, 0 when it is down
+	shield: f64,
+	shield_label: Sprite,
+	// Seconds since the player spawned, used to animate the HUD
+	time: f64,
+	exhaust: ParticleEmitter,
+	score: usize,
+	score_label: Sprite,
+	// Kills in quick succession multiply the points they are worth
+	combo: Combo,
+}
+
+// The side of the screen a ship flies from, and faces away from
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+	Left,
+	// Mirrors the left side, in versus
+	Right,
+}
+
+impl Side {
+	// Direction the ship fires in, in radians
+	fn heading(self) -> f64 {
+		match self {
+			Side::Left => 0.0,
+			Side::Right => PI,
+		}
+	}
 }
 
 // Player's Ship's Sprite frames
@@ -56,8 +251,15 @@ enum PlayerFrame {
 }
 
 impl Player {
-	pub fn new(phi: &mut Phi) -> Player {
-		// Set up player sprites and spawn him at (64,center) with default cannon
+	// Sets up the `index`th of `count` players. Ships are spawned on the left, spread evenly
+	// from top to bottom, with the first weapon. In versus, the second player faces the first
+	// one from the right.
+	pub fn new(phi: &mut Phi, index: usize, count: usize, versus: bool) -> Player {
+		let weapons = WeaponDef::load_all(WEAPONS_PATH).unwrap();
+		if weapons.is_empty() {
+			panic!("No valid weapon in {}", WEAPONS_PATH);
+		}
+
 		let spritesheet = Sprite::load(&mut phi.renderer, PLAYER_PATH).unwrap();
 		let mut sprites = Vec::with_capacity(9);
 
@@ -72,55 +274,110 @@ impl Player {
             }
         }
 
-        Player {
-            rect: Rectangle {
-                x: 64.0,
-                y: (phi.output_size().1 - PLAYER_H) / 2.0,
-                w: PLAYER_W,
-                h: PLAYER_H,
+        let weapon_states = weapons.iter().map(WeaponState::new).collect();
+        let weapon_levels = vec![0; weapons.len()];
+        let weapon_labels = weapons.iter().map(|weapon|
+            phi.ttf_str_sprite(&weapon.name, HUD_FONT, HUD_FONT_SIZE, Color::RGB(220, 220, 200)).unwrap())
+            .collect();
+
+        let (win_w, win_h) = AREA;
+        let side = if versus && index % 2 == 1 { Side::Right } else { Side::Left };
+        let spawn = Rectangle {
+            x: match side {
+                Side::Left => 64.0,
+                Side::Right => win_w - 64.0 - PLAYER_W,
             },
+            y: if versus { (win_h - PLAYER_H) / 2.0 }
+               else { (win_h - PLAYER_H) * (index + 1) as f64 / (count + 1) as f64 },
+            w: PLAYER_W,
+            h: PLAYER_H,
+        };
+
+        // The engine's flames trail behind the ship
+        let mut exhaust = ParticleEmitter::new(EXHAUST, 64);
+        exhaust.descr.direction = (side.heading() + PI).to_degrees();
+
+        let score_label = phi.ttf_str_sprite(&format!("P{}: 0", index + 1),
+            HUD_FONT, HUD_FONT_SIZE, Color::RGB(220, 220, 200)).unwrap();
+
+        Player {
+            index: index,
+            id: index + 1,
+            bindings: Bindings::for_player(index),
+            tint: PLAYER_TINTS[index % PLAYER_TINTS.len()],
+            side: side,
+            region: if versus { VERSUS_REGION } else { PLAYER_REGION },
+            rect: spawn,
+            spawn: spawn,
             sprites: sprites,
             current: PlayerFrame::MidNorm,
-            cannon: CannonType::RectBullet,
+            weapons: weapons,
+            weapon_states: weapon_states,
+            weapon_labels: weapon_labels,
+            weapon: 0,
+            weapon_levels: weapon_levels,
             lives: PLAYER_MAX_LIVES,
+            flash_time: 0.0,
+            shield: 0.0,
+            shield_label: phi.ttf_str_sprite("Shield", HUD_FONT, HUD_FONT_SIZE, Color::RGB(220, 220, 200)).unwrap(),
+            time: 0.0,
+            exhaust: exhaust,
+            score: 0,
+            score_label: score_label,
+            combo: Combo::new(),
         }
 	}
 
+	// Players who lost all their lives are out until they are revived
+	pub fn alive(&self) -> bool {
+		self.lives > 0
+	}
+
+	// Brings the ship back where it first spawned
+	pub fn respawn(&mut self, lives: usize) {
+		self.lives = lives;
+		self.rect = self.spawn;
+		self.shield = 0.0;
+		self.flash_time = PLAYER_FLASH_DURATION;
+	}
+
 	// Checks for weapon changes, if the player is trying to go off screen, and updates speed
-	pub fn update(&mut self, phi: &mut Phi, elapsed: f64) {
-		if phi.events.now.key_1 == Some(true) {
-			self.cannon = CannonType::RectBullet;
+	pub fn update(&mut self, controls: Controls, elapsed: f64) {
+		self.flash_time = (self.flash_time - elapsed).max(0.0);
+		self.shield = (self.shield - elapsed).max(0.0);
+		self.time += elapsed;
+		self.combo.update(elapsed);
+
+		if let Some(index) = controls.weapon {
+			if index < self.weapons.len() {
+				self.weapon = index;
+			}
 		}
 
-		if phi.events.now.key_2 == Some(true) {
-			self.cannon = CannonType::SineBullet {
-				amplitude: 10.0,
-				angular_vel: 15.0,
-			};
+		if controls.next_weapon {
+			self.weapon = (self.weapon + 1) % self.weapons.len();
 		}
 
-		if phi.events.now.key_3 == Some(true) {
-			self.cannon = CannonType::DivergentBullet {
-				a: 100.0,
-				b: 1.2,
-			};			
+		// Every weapon cools down and reloads, even when it is not held
+		for (state, weapon) in self.weapon_states.iter_mut().zip(self.weapons.iter()) {
+			state.update(weapon, elapsed);
 		}
 
 		let diagonal = 
-			(phi.events.key_up ^ phi.events.key_down) &&
-			(phi.events.key_left ^ phi.events.key_right);
+			(controls.up ^ controls.down) &&
+			(controls.left ^ controls.right);
 
 		let moved = 
 			if diagonal { 1.0 / 2.0f64.sqrt() }
 			else { 1.0 } * PLAYER_SPEED * elapsed;
 
-		let dx = match (phi.events.key_left, phi.events.key_right) {
+		let dx = match (controls.left, controls.right) {
 			(true, true) | (false, false) => 0.0,
 			(true, false) => -moved,
 			(false, true) => moved,
 		};
 
-		let dy = match (phi.events.key_up, phi.events.key_down) {
+		let dy = match (controls.up, controls.down) {
 			(true, true) | (false, false) => 0.0,
 			(true, false) => -moved,
 			(false, true) => moved,
@@ -129,15 +386,35 @@ impl Player {
 		self.rect.x += dx;
 		self.rect.y += dy;
 
+		// Ships on the right move in the mirror image of the left side's region
+		let (win_w, win_h) = AREA;
+		let region_w = win_w * self.region;
+
 		let movable_region = Rectangle {
-			x: 0.0,
+			x: match self.side {
+				Side::Left => 0.0,
+				Side::Right => win_w - region_w,
+			},
 			y: 0.0,
-			w: phi.output_size().0 * 0.70,
-			h: phi.output_size().1,
+			w: region_w,
+			h: win_h,
 		};
 
 		self.rect = self.rect.move_inside(movable_region).unwrap();
 
+		// The engine sits at the back of the ship
+		self.exhaust.position = match self.side {
+			Side::Left => (self.rect.x + 4.0, self.rect.center().1),
+			Side::Right => (self.rect.x + PLAYER_W - 4.0, self.rect.center().1),
+		};
+		self.exhaust.update(elapsed);
+
+		// Going forwards means going right, unless the ship faces left
+		let dx = match self.side {
+			Side::Left => dx,
+			Side::Right => -dx,
+		};
+
 		self.current = 
 			if dx == 0.0 && dy < 0.0 		{ PlayerFrame::UpNorm }
 			else if dx > 0.0 && dy < 0.0 	{ PlayerFrame::UpFast }
@@ -153,30 +430,281 @@ impl Player {
 
 	// Draw the player to the screen
 	pub fn render(&self, phi: &mut Phi) {
+		self.exhaust.render(&mut phi.queue, Layer::Entities, PLAYER_Z - 1);
+
 		if DEBUG {
-			phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
-			phi.renderer.fill_rect(self.rect.to_sdl().unwrap());
+			phi.queue.fill_rect(Layer::Entities, PLAYER_Z, self.rect, Color::RGB(200, 200, 50));
 		}
 
-		phi.renderer.copy_sprite(
+		// Alternate between a red tint and the normal colors while damaged
+		let flashing = self.flash_time > 0.0 &&
+			(self.flash_time * PLAYER_FLASH_FPS) as usize % 2 == 0;
+
+		let tint = if flashing { Color::RGB(255, 60, 60) } else { self.tint };
+		let mirrored = self.side == Side::Right;
+
+		phi.queue.sprite_ex(Layer::Entities, PLAYER_Z,
 			&self.sprites[self.current as usize],
-			self.rect);
+			self.rect,
+			DrawParams::new().tinted(tint).flipped(mirrored, false));
+
+		// The shield glows around the ship, and blinks during its last seconds
+		if self.shield > 0.0 && (self.shield > 2.0 || (self.shield * 8.0) as usize % 2 == 0) {
+			let glow = Rectangle::with_size(PLAYER_W * 1.4, PLAYER_H * 1.4).center_at(self.rect.center());
+			phi.queue.sprite_ex(Layer::Entities, PLAYER_Z + 1,
+				&self.sprites[self.current as usize],
+				glow,
+				DrawParams::new()
+					.flipped(mirrored, false)
+					.tinted(PowerUpKind::Shield.color())
+					.with_blend(BlendMode::Add)
+					.with_opacity(0.6));
+		}
+	}
+
+	// Starts the damage flash, the shield taking the hit if it is up.
+	// Returns whether a life was lost.
+	pub fn take_hit(&mut self) -> bool {
+		self.flash_time = PLAYER_FLASH_DURATION;
+
+		if self.shield > 0.0 {
+			self.shield = 0.0;
+			false
+		} else {
+			self.lives -= 1;
+			true
+		}
+	}
+
+	// Applies a collected power-up, except for smart bombs and revives which affect the
+	// whole screen and the other players
+	pub fn power_up(&mut self, kind: PowerUpKind) {
+		match kind {
+			PowerUpKind::Shield => self.shield = SHIELD_DURATION,
+			PowerUpKind::ExtraLife => self.lives = ::std::cmp::min(self.lives + 1, PLAYER_LIVES_CAP),
+			PowerUpKind::WeaponUp => {
+				let level = &mut self.weapon_levels[self.weapon];
+				if *level < MAX_WEAPON_LEVEL {
+					*level += 1;
+					self.weapons[self.weapon].level_up(*level);
+				}
+			},
+			PowerUpKind::SmartBomb | PowerUpKind::Revive => {},
+		}
+	}
+
+	// Adds points to the score and redraws it
+	fn add_score(&mut self, phi: &mut Phi, points: usize) {
+		self.score += points;
+		self.score_label = phi.ttf_str_sprite(&format!("P{}: {}", self.index + 1, self.score),
+			HUD_FONT, HUD_FONT_SIZE, Color::RGB(220, 220, 200)).unwrap();
+	}
+
+	// Counts a kill worth `points` through the combo, and returns the points it scored
+	pub fn score_kill(&mut self, phi: &mut Phi, points: usize) -> usize {
+		let points = self.combo.kill(points);
+		self.add_score(phi, points);
+		points
 	}
 
-	// Spawns two bullets based on cannon type on top of the player's two cannons
-	pub fn spawn_bullets(&self) -> Vec<Box<Bullet>> {
-		let cannons_x = self.rect.x + 30.0;
-		let cannon1_y = self.rect.y + 6.0;
-		let cannon2_y = self.rect.y + PLAYER_H - 10.0;
-		spawn_bullets(self.cannon, cannons_x, cannon1_y, cannon2_y)
+	// The ship cannot be hurt again while it flashes, e.g. while going through a laser
+	pub fn vulnerable(&self) -> bool {
+		self.flash_time == 0.0
+	}
+
+	// Returns the point between the player's two cannons
+	pub fn cannons_center(&self) -> (f64, f64) {
+		match self.side {
+			Side::Left => (self.rect.x + 30.0, self.rect.y + PLAYER_H / 2.0),
+			Side::Right => (self.rect.x + PLAYER_W - 30.0, self.rect.y + PLAYER_H / 2.0),
+		}
+	}
+
+	// Describes the ship to the rival's bullets and beams
+	pub fn target(&self) -> Target {
+		Target {
+			id: self.id,
+			rect: self.rect,
+		}
+	}
+
+	// Fires the current weapon if its cooldown, heat and ammo allow it.
+	// Beams are held for dt seconds and stop on the first target in front of the cannons.
+	pub fn try_fire<F>(&mut self, targets: &[Target], area: (f64, f64), dt: f64, fire: F) -> Option<Shot>
+	where F: FnMut(BulletKind) {
+		let weapon = &self.weapons[self.weapon];
+
+		match weapon.kind {
+			WeaponKind::Bullets(_) => {
+				if self.weapon_states[self.weapon].try_fire(weapon) {
+					self.spawn_bullets(fire);
+					Some(Shot::Bullets)
+				} else {
+					None
+				}
+			},
+			WeaponKind::Beam { .. } => {
+				if self.weapon_states[self.weapon].try_sustain(weapon, dt) {
+					Some(Shot::Beam(cast_beam(weapon, self.cannons_center(), self.side.heading(), targets, area)))
+				} else {
+					None
+				}
+			},
+		}
+	}
+
+	// Draws the player's HUD in the column starting at `x`, from the bottom up: the current
+	// weapon's name and, if it has one, its heat or ammo meter, then its upgrades, the lives
+	// left and the shield's timer, and the score with the combo's multiplier and timer
+	pub fn render_hud(&self, phi: &mut Phi, x: f64, multiplier_labels: &[Sprite]) {
+		let (_, win_h) = phi.output_size();
+		let x = x + HUD_MARGIN;
+
+		let label = &self.weapon_labels[self.weapon];
+		let (label_w, label_h) = label.size();
+		let y = win_h - HUD_MARGIN - label_h;
+
+		let icon_w = PLAYER_W * LIFE_ICON_SCALE;
+		let icon_h = PLAYER_H * LIFE_ICON_SCALE;
+		let lives_y = y - METER_H * 1.5 - icon_h;
+
+		for i in 0..self.lives {
+			phi.queue.sprite_ex(Layer::Hud, 1, &self.sprites[PlayerFrame::MidNorm as usize], Rectangle {
+				x: x + (icon_w + HUD_MARGIN / 2.0) * i as f64,
+				y: lives_y,
+				w: icon_w,
+				h: icon_h,
+			}, DrawParams::new().tinted(self.tint));
+		}
+
+		if self.shield > 0.0 {
+			let (shield_w, shield_h) = self.shield_label.size();
+			let shield_x = x + (icon_w + HUD_MARGIN / 2.0) * self.lives as f64 + HUD_MARGIN;
+			let shield_y = lives_y + (icon_h - shield_h) / 2.0;
+
+			phi.queue.sprite(Layer::Hud, 1, &self.shield_label, Rectangle {
+				x: shield_x,
+				y: shield_y,
+				w: shield_w,
+				h: shield_h,
+			});
+
+			let frame = Rectangle {
+				x: shield_x + shield_w + HUD_MARGIN,
+				y: shield_y + (shield_h - METER_H) / 2.0,
+				w: METER_W,
+				h: METER_H,
+			};
+
+			phi.queue.fill_rect(Layer::Hud, 0, frame, Color::RGB(40, 40, 40));
+			fill_meter(phi, frame, self.shield / SHIELD_DURATION, PowerUpKind::Shield.color());
+		}
+
+		let (score_w, score_h) = self.score_label.size();
+		let score_y = lives_y - HUD_MARGIN / 2.0 - score_h;
+
+		phi.queue.sprite(Layer::Hud, 1, &self.score_label, Rectangle {
+			x: x,
+			y: score_y,
+			w: score_w,
+			h: score_h,
+		});
+
+		let multiplier = &multiplier_labels[self.combo.multiplier() - 1];
+		let (multiplier_w, multiplier_h) = multiplier.size();
+
+		phi.queue.sprite(Layer::Hud, 1, multiplier, Rectangle {
+			x: x + score_w + HUD_MARGIN,
+			y: score_y + (score_h - multiplier_h) / 2.0,
+			w: multiplier_w,
+			h: multiplier_h,
+		});
+
+		// The bar empties as the multiplier is about to drop
+		fill_meter(phi, Rectangle {
+			x: x + score_w + multiplier_w + HUD_MARGIN * 2.0,
+			y: score_y + (score_h - METER_H) / 2.0,
+			w: METER_W,
+			h: METER_H,
+		}, self.combo.time_left(), PowerUpKind::WeaponUp.color());
+
+		phi.queue.sprite(Layer::Hud, 1, label, Rectangle {
+			x: x,
+			y: y,
+			w: label_w,
+			h: label_h,
+		});
+
+		// One pip above the name for every upgrade
+		for i in 0..self.weapon_levels[self.weapon] {
+			phi.queue.fill_rect(Layer::Hud, 1, Rectangle {
+				x: x + i as f64 * METER_H * 1.5,
+				y: y - METER_H,
+				w: METER_H,
+				h: METER_H / 2.0,
+			}, PowerUpKind::WeaponUp.color());
+		}
+
+		let meter = match self.weapon_states[self.weapon].meter(&self.weapons[self.weapon]) {
+			Some(meter) => meter,
+			None => return,
+		};
+
+		let frame = Rectangle {
+			x: x + label_w + HUD_MARGIN,
+			y: y + (label_h - METER_H) / 2.0,
+			w: METER_W,
+			h: METER_H,
+		};
+
+		let color = match self.weapons[self.weapon].resource {
+			// Blinks while the weapon cannot fire
+			_ if meter.blocked && (self.time * 8.0) as usize % 2 == 0 =>
+				Color::RGB(255, 255, 255),
+			WeaponResource::Heat { .. } =>
+				Color::RGB(230, (230.0 * (1.0 - meter.fill)) as u8, 30),
+			_ =>
+				Color::RGB(60, 140, 230),
+		};
+
+		phi.queue.fill_rect(Layer::Hud, 0, frame, Color::RGB(40, 40, 40));
+		fill_meter(phi, frame, meter.fill, color);
+	}
+
+	// Spawns the current weapon's bullets on top of the player's two cannons, handing them
+	// to `fire`
+	pub fn spawn_bullets<F>(&self, mut fire: F)
+	where F: FnMut(BulletKind) {
+		let cannons_x = match self.side {
+			Side::Left => self.rect.x + 30.0,
+			Side::Right => self.rect.x + PLAYER_W - 30.0 - BULLET_W,
+		};
+		let upper = (cannons_x, self.rect.y + 6.0);
+		let lower = (cannons_x, self.rect.y + PLAYER_H - 10.0);
+
+		// Patterns start from the cannon on the ship's left, the lower one once it is turned around
+		let (cannon1, cannon2) = match self.side {
+			Side::Left => (upper, lower),
+			Side::Right => (lower, upper),
+		};
+
+		let owner = self.index;
+		spawn_bullets(&self.weapons[self.weapon], cannon1, cannon2, self.side.heading(), |mut bullet| {
+			bullet.impact_mut().owner = Some(owner);
+			fire(bullet);
+		});
 	}
 }
 
 // Asteroid Implementation
 struct Asteroid {
+	// Unique for the whole game, lets homing bullets keep track of their target.
+	// Shared with the enemies' ids.
+	id: usize,
 	sprite: AnimatedSprite,
 	rect: Rectangle,
 	vel: f64,
+	hp: f64,
 }
 
 impl Asteroid {
@@ -195,32 +723,35 @@ impl Asteroid {
 		}
 	}
 
-	// Updates location and check if offscreen
-    fn update(mut self, dt: f64) -> Option<Asteroid> {
+	// Updates location, returns false once offscreen
+    fn update(&mut self, dt: f64) -> bool {
         self.rect.x -= dt * self.vel;
         self.sprite.add_time(dt);
 
-        if self.rect.x <= -ASTEROID_SIDE {
-            None
-        } else {
-            Some(self)
-        }
+        self.rect.x > -self.rect.w
     }
 
     // Draws asteroid to screen
 	fn render(&mut self, phi: &mut Phi) {
         if DEBUG {
-            phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
-            phi.renderer.fill_rect(self.rect().to_sdl().unwrap());
+            phi.queue.fill_rect(Layer::Entities, ASTEROID_Z, self.rect(), Color::RGB(200, 200, 50));
         }
 
-        phi.renderer.copy_sprite(&self.sprite, self.rect);
+        phi.queue.sprite(Layer::Entities, ASTEROID_Z, &self.sprite, self.rect);
 	}
 
 	// returns asteroid's rectangle (x, y, w, h)
 	fn rect(&self) -> Rectangle {
 		self.rect
 	}
+
+	// Describes the asteroid to the bullets that may aim at it
+	fn target(&self) -> Target {
+		Target {
+			id: self.id,
+			rect: self.rect,
+		}
+	}
 }
 
 // Asteroid Factory Implementation
@@ -230,21 +761,30 @@ struct AsteroidFactory {
 
 impl AsteroidFactory {
 	// Selects a random y location and speed for a generated asteroid
-	fn random(&self, phi: &mut Phi) -> Asteroid {
-		let (w, h) = phi.output_size();
+	fn random(&self, id: usize) -> Asteroid {
+		self.spawn(id, AsteroidSize::Large, None, None)
+	}
+
+	// Spawns an asteroid past the right edge, at a fraction `y` of the screen's height.
+	// The height and speed are random when not given.
+	fn spawn(&self, id: usize, size: AsteroidSize, y: Option<f64>, speed: Option<f64>) -> Asteroid {
+		let (w, h) = AREA;
+		let side = ASTEROID_SIDE * size.scale();
 
 		let mut sprite = self.sprite.clone();
 		sprite.set_fps(::rand::random::<f64>().abs() * 20.0 + 10.0);
 
 		Asteroid {
+			id: id,
 			sprite: sprite,
 			rect: Rectangle {
-				w: ASTEROID_SIDE,
-				h: ASTEROID_SIDE,
+				w: side,
+				h: side,
 				x: w,
-				y: ::rand::random::<f64>().abs() * (h - ASTEROID_SIDE),
+				y: y.unwrap_or_else(|| ::phi::rng::random::<f64>().abs()) * (h - side),
 			},
-			vel: ::rand::random::<f64>().abs() * 100.0 + 50.0,
+			vel: speed.unwrap_or_else(|| ::phi::rng::random::<f64>().abs() * 100.0 + 50.0),
+			hp: ASTEROID_HP * size.scale(),
 		}
 	}
 }
@@ -273,20 +813,19 @@ impl Explosion {
 		}
 	}
 
-	// updates the animation of the explosion
-	fn update(mut self, dt: f64) -> Option<Explosion> {
+	// updates the animation of the explosion, returns false once it is over
+	fn update(&mut self, dt: f64) -> bool {
 		self.alive_since += dt;
 		self.sprite.add_time(dt);
 
-		if self.alive_since >= EXPLOSION_DURATION {
-			None
-		} else {
-			Some(self)
-		}
+		self.alive_since < EXPLOSION_DURATION
 	}
 
+	// Draws the explosion, fading it out over its lifetime
 	fn render(&self, phi: &mut Phi) {
-		phi.renderer.copy_sprite(&self.sprite, self.rect);
+		let opacity = 1.0 - self.alive_since / EXPLOSION_DURATION;
+		phi.queue.sprite_ex(Layer::Effects, 0, &self.sprite, self.rect,
+			DrawParams::new().with_opacity(opacity));
 	}
 }
 
@@ -313,183 +852,1059 @@ impl ExplosionFactory {
 
 // The Game View
 pub struct GameView {
-	player: Player,
-	bullets: Vec<Box<Bullet>>,
-	asteroids: Vec<Asteroid>,
+	players: Vec<Player>,
+	bullets: Pool<BulletKind>,
+	// The beams fired during the current frame
+	beams: Vec<Beam>,
+	beam_sprite: AnimatedSprite,
+	asteroids: Pool<Asteroid>,
 	asteroid_factory: AsteroidFactory,
-
-	explosions: Vec<Explosion>,
+	enemies: Pool<Enemy>,
+	enemy_factory: EnemyFactory,
+	// Last id given to a player, an asteroid, an enemy or a part of the boss
+	next_id: usize,
+
+	boss: Option<Boss>,
+	boss_sprite: Sprite,
+	boss_label: Sprite,
+
+	// Where every asteroid, enemy and boss comes from
+	mode: Mode,
+	// The player who last damaged each asteroid, enemy and part of the boss, by id, and
+	// scores it once it is destroyed
+	last_hit: HashMap<usize, usize>,
+	// "x1" to "x8", shown next to the scores
+	multiplier_labels: Vec<Sprite>,
+	popups: Pool<ScorePopup>,
+	// Shows the current difficulty, toggled with F3
+	debug_overlay: bool,
+	// The overlay's lines and their sprites, only rendered again when their text changes
+	debug_lines: Vec<(String, Sprite)>,
+	// Announcement shown in the middle of the screen, and the time it has left
+	banner: Option<(Sprite, f64)>,
+	checkpoint_label: Sprite,
+	complete_label: Sprite,
+	// Time left before going back to the menu, once the level is complete or a versus game won
+	level_end: Option<f64>,
+
+	explosions: Pool<Explosion>,
 	explosion_factory: ExplosionFactory,
 
+	powerups: Pool<PowerUp>,
+	// The letter drawn on every kind of power-up
+	powerup_letters: Vec<(PowerUpKind, Sprite)>,
+
+	debris: ParticleEmitter,
+	sparks: ParticleEmitter,
+
 	bg_back: Background,
 	bg_middle: Background,
 	bg_front: Background,
 }
 
+// Where the asteroids and enemies come from
+enum Mode {
+	// A level script, played once through
+	Level(Level),
+	// Endless waves, harder and harder as the game goes on
+	Endless(Difficulty),
+	// Players fighting each other among asteroids, with a difficulty file of its own
+	Versus(Difficulty),
+}
+
 impl GameView {
-	// Starts the game view with a new player, new generators, and the backgrounds
-	pub fn new(phi: &mut Phi) -> GameView {
+	// Starts the game view with `players` new players, new generators, and the backgrounds
+	pub fn new(phi: &mut Phi, players: usize) -> GameView {
+		GameView::with_level(phi, LEVEL_PATH, players)
+	}
+
+	// Same as `new`, playing the given level file
+	pub fn with_level(phi: &mut Phi, level_path: &str, players: usize) -> GameView {
+		let level = Level::load(level_path).unwrap();
+		let title = level.name.clone();
+		GameView::with_mode(phi, Mode::Level(level), &title, players, ::rand::random())
+	}
+
+	// Same as `new`, in endless mode
+	pub fn endless(phi: &mut Phi, players: usize) -> GameView {
+		let curve = DifficultyCurve::load(DIFFICULTY_PATH).unwrap();
+		GameView::with_mode(phi, Mode::Endless(Difficulty::new(curve)), "Endless", players, ::rand::random())
+	}
+
+	// Two players fighting each other from either side of the screen, the last one standing
+	// winning
+	pub fn versus(phi: &mut Phi) -> GameView {
+		let curve = DifficultyCurve::load(VERSUS_PATH).unwrap();
+		GameView::with_mode(phi, Mode::Versus(Difficulty::new(curve)), "Versus", 2, ::rand::random())
+	}
+
+	// Two players going through the level together over the network, the second ship being
+	// the joining player's. Both peers must start from the same `seed` to see the same game.
+	pub fn networked(phi: &mut Phi, seed: u32) -> GameView {
+		let level = Level::load(LEVEL_PATH).unwrap();
+		let title = level.name.clone();
+		GameView::with_mode(phi, Mode::Level(level), &title, 2, seed)
+	}
+
+	fn with_mode(phi: &mut Phi, mode: Mode, title: &str, players: usize, seed: u32) -> GameView {
+		::phi::rng::seed(seed);
+		let label_color = Color::RGB(220, 220, 200);
+		let versus = match mode {
+			Mode::Versus(_) => true,
+			_ => false,
+		};
+
+		// Announce the level by its name, if it has one
+		let banner = if title.is_empty() { None } else {
+			phi.ttf_str_sprite(title, HUD_FONT, HUD_FONT_SIZE * 2, label_color)
+				.map(|label| (label, BANNER_DURATION))
+		};
+
+		// Asteroids share a single spritesheet, so group their draws together
+		phi.queue.set_batching(true);
+		phi.audio.play_music(GAME_MUSIC);
+
+		// Enemies and bosses fly the player's ship, facing the other way
+		let ship = Sprite::load(&mut phi.renderer, PLAYER_PATH).unwrap()
+			.region(Rectangle {
+				x: 0.0,
+				y: PLAYER_H * 1.0,
+				w: PLAYER_W,
+				h: PLAYER_H,
+			}).unwrap();
+
 		GameView {
-			player: Player::new(phi),
+			players: (0..players).map(|index| Player::new(phi, index, players, versus)).collect(),
+
+			bullets: Pool::with_capacity(256),
 
-			bullets: vec![],
+			beams: vec![],
 
-			asteroids: vec![],
+			beam_sprite: AnimatedSprite::with_fps(
+				AnimatedSprite::load_frames(phi, AnimatedSpriteDescr {
+					image_path: BEAM_PATH,
+					total_frames: BEAM_FRAMES,
+					frames_high: 1,
+					frames_wide: BEAM_FRAMES,
+					frame_w: BEAM_FRAME_W,
+					frame_h: BEAM_FRAME_H,
+				}), BEAM_FPS),
+
+			asteroids: Pool::with_capacity(64),
 
 			asteroid_factory: Asteroid::factory(phi),
 
-			explosions: vec![],
+			enemies: Pool::with_capacity(32),
+
+			enemy_factory: EnemyFactory::new(ship.clone()),
+
+			next_id: players,
+
+			boss: None,
+
+			boss_sprite: ship,
+
+			boss_label: phi.ttf_str_sprite(BOSS_NAME, HUD_FONT, HUD_FONT_SIZE, label_color).unwrap(),
+
+			mode: mode,
+
+			last_hit: HashMap::new(),
+
+			multiplier_labels: (1..score::MAX_MULTIPLIER + 1).map(|multiplier|
+				phi.ttf_str_sprite(&format!("x{}", multiplier), HUD_FONT, HUD_FONT_SIZE, Color::RGB(255, 200, 60)).unwrap())
+				.collect(),
+
+			popups: Pool::with_capacity(32),
+
+			debug_overlay: false,
+
+			debug_lines: Vec::new(),
+
+			banner: banner,
+
+			checkpoint_label: phi.ttf_str_sprite("Checkpoint", HUD_FONT, HUD_FONT_SIZE * 2, label_color).unwrap(),
+
+			complete_label: phi.ttf_str_sprite("Level Complete", HUD_FONT, HUD_FONT_SIZE * 2, label_color).unwrap(),
+
+			level_end: None,
+
+			explosions: Pool::with_capacity(64),
 
 			explosion_factory: Explosion::factory(phi),
 
+			powerups: Pool::with_capacity(8),
+
+			powerup_letters: PowerUpKind::all().iter().map(|&kind|
+				(kind, phi.ttf_str_sprite(kind.letter(), HUD_FONT, HUD_FONT_SIZE, Color::RGB(255, 255, 255)).unwrap()))
+				.collect(),
+
+			debris: ParticleEmitter::new(DEBRIS, 512),
+
+			sparks: ParticleEmitter::new(SPARKS, 256),
+
 			bg_back: Background {
 				pos: 0.0,
 				vel: 20.0,
+				parallax: 0.25,
 				sprite: Sprite::load(&mut phi.renderer, "assets/starBG.png").unwrap(),
 			},
 
 			bg_middle: Background {
 				pos: 0.0,
 				vel: 40.0,
+				parallax: 0.5,
 				sprite: Sprite::load(&mut phi.renderer, "assets/starMG.png").unwrap(),
 			},
 
 			bg_front: Background {
 				pos: 0.0,
 				vel: 80.0,
+				parallax: 1.0,
 				sprite: Sprite::load(&mut phi.renderer, "assets/starFG.png").unwrap(),
 			},
 		}
 	}
 }
 
-impl View for GameView {
+impl GameView {
+	fn new_id(&mut self) -> usize {
+		self.next_id += 1;
+		self.next_id
+	}
 
-	// Displays and updates every entity
-	fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
-		// Check if the X clicked on the screen
-		if phi.events.now.quit {
-			return ViewAction::Quit;
+	// Whether the players fight each other rather than side by side
+	fn rivals(&self) -> bool {
+		match self.mode {
+			Mode::Versus(_) => true,
+			_ => false,
 		}
+	}
 
-		// Goes back to the main menu if esc pressed
-		if phi.events.now.key_escape == Some(true) {
-			return ViewAction::ChangeView(Box::new(
-				::views::main_menu::MainMenuView::new(phi)));
+	// Describes the ships still in the game, which rivals aim at
+	fn player_targets(&self) -> Vec<Target> {
+		self.players.iter()
+			.filter(|player| player.alive())
+			.map(Player::target)
+			.collect()
+	}
+
+	// Describes every asteroid, enemy and part of the boss to the bullets which may aim at them
+	fn targets(&self) -> Vec<Target> {
+		let mut targets: Vec<Target> = self.asteroids.iter().map(|(_, a)| a.target())
+			.chain(self.enemies.iter().map(|(_, e)| e.target()))
+			.collect();
+
+		if let Some(ref boss) = self.boss {
+			targets.extend(boss.targets());
+		}
+
+		targets
+	}
+
+	// Destroys every asteroid and enemy, which explode and score for the `player` who set
+	// the bomb off during the next collision check, along with the enemies' bullets, and
+	// damages the boss
+	fn smart_bomb(&mut self, phi: &mut Phi, player: usize) {
+		for target in self.targets() {
+			self.last_hit.insert(target.id, player);
 		}
 
-		self.player.update(phi, elapsed);
+		for (_, asteroid) in self.asteroids.iter_mut() {
+			asteroid.hp = 0.0;
+		}
+		for (_, enemy) in self.enemies.iter_mut() {
+			enemy.hp = 0.0;
+		}
+		if let Some(ref mut boss) = self.boss {
+			for target in boss.targets() {
+				boss.damage(target.id, SMART_BOMB_DAMAGE);
+			}
+		}
 
-		// Update all the entities and refill the vecs with only alive entities
-		let old_bullets = ::std::mem::replace(&mut self.bullets, vec![]);
+		// Rivals' bullets are blown away too
+		let rivals = self.rivals();
+		self.bullets.retain(|bullet| bullet.team() != Team::Enemy &&
+			!(rivals && bullet.impact().owner != Some(player)));
+		phi.camera.add_trauma(SMART_BOMB_TRAUMA);
+	}
 
-		self.bullets = 
-			old_bullets.into_iter()
-			.filter_map(|bullet| bullet.update(phi, elapsed))
-			.collect();
+	// Whether every asteroid, enemy and boss is gone
+	fn clear(&self) -> bool {
+		self.asteroids.is_empty() && self.enemies.is_empty() && self.boss.is_none()
+	}
+
+	// Hurts a player, unless their ship still flashes from the last hit
+	fn hit_player(&mut self, phi: &mut Phi, index: usize) {
+		let player = &mut self.players[index];
+		if !player.vulnerable() {
+			return;
+		}
+
+		player.combo.reset();
+		phi.camera.add_trauma(HIT_TRAUMA);
+		phi.audio.play_sound(HIT_SOUND);
 
-		self.asteroids =
-			::std::mem::replace(&mut self.asteroids, vec![])
-			.into_iter()
-			.filter_map(|asteroid| asteroid.update(elapsed))
+		if player.take_hit() {
+			println!("Player {} Lives: {}", player.index + 1, player.lives);
+
+			if let Mode::Endless(ref mut difficulty) = self.mode {
+				difficulty.set_back();
+			}
+		}
+	}
+
+	// Forgets who hit whatever is gone
+	fn forget_hits(&mut self) {
+		let targets = self.targets();
+		self.last_hit = self.last_hit.drain()
+			.filter(|&(id, _)| targets.iter().any(|target| target.id == id))
 			.collect();
+	}
+
+	// Scores every kill, given as the player who made it, where it happened and its base
+	// points, through that player's combo. Popups take the color of the player's ship.
+	fn score_kills(&mut self, phi: &mut Phi, kills: Vec<(usize, (f64, f64), usize)>) {
+		for (killer, center, points) in kills {
+			let player = &mut self.players[killer];
+			let points = player.score_kill(phi, points);
+
+			let label = phi.ttf_str_sprite(&format!("+{}", points),
+				HUD_FONT, POPUP_FONT_SIZE, player.tint).unwrap();
+			self.popups.insert(ScorePopup::new(label, center));
+		}
+	}
+
+	// Spawns whatever the level script, or the difficulty in endless mode, asks for
+	fn spawn(&mut self, elapsed: f64) {
+		let area = AREA;
+		let clear = self.clear();
+		// Players make the game harder together
+		let score = self.players.iter().fold(0, |score, player| score + player.score);
+		let boss = self.boss.is_some();
+
+		let events = match self.mode {
+			Mode::Level(ref mut level) => level.update(clear, elapsed),
+			Mode::Endless(ref mut difficulty) | Mode::Versus(ref mut difficulty) =>
+				difficulty.update(score, boss, elapsed),
+		};
 
-		self.explosions =
-			::std::mem::replace(&mut self.explosions, vec![])
-			.into_iter()
-			.filter_map(|explosion| explosion.update(elapsed))
+		for event in events {
+			match event {
+				Event::Asteroid { size, y, speed } => {
+					let id = self.new_id();
+					self.asteroids.insert(self.asteroid_factory.spawn(id, size, y, speed));
+				},
+				Event::Enemies(wave) => {
+					let next_id = &mut self.next_id;
+					let mut ids = || { *next_id += 1; *next_id };
+
+					for enemy in self.enemy_factory.wave(&wave, area, &mut ids) {
+						self.enemies.insert(enemy);
+					}
+				},
+				Event::Boss => {
+					let next_id = &mut self.next_id;
+					let mut ids = || { *next_id += 1; *next_id };
+					self.boss = Some(Boss::new(self.boss_sprite.clone(), area, &mut ids));
+				},
+				Event::Checkpoint => {
+					self.banner = Some((self.checkpoint_label.clone(), BANNER_DURATION));
+				},
+			}
+		}
+
+		if let Mode::Level(ref level) = self.mode {
+			if level.complete() && self.level_end.is_none() {
+				self.banner = Some((self.complete_label.clone(), LEVEL_END_DELAY));
+				self.level_end = Some(LEVEL_END_DELAY);
+			}
+		}
+	}
+
+	// Lists the current difficulty parameters in the top right corner
+	fn render_debug_overlay(&mut self, phi: &mut Phi) {
+		let params = match self.mode {
+			Mode::Endless(ref difficulty) | Mode::Versus(ref difficulty) if self.debug_overlay =>
+				difficulty.parameters(),
+			_ => return,
+		};
+
+		let lines = [
+			format!("difficulty {:.2}", params.difficulty),
+			format!("asteroids/s {:.2}", params.asteroid_rate),
+			format!("speed {:.0}-{:.0}", params.speed.0, params.speed.1),
+			format!("sizes S {:.2} M {:.2} L {:.2}", params.sizes.0, params.sizes.1, params.sizes.2),
+			format!("waves/min {:.2}", params.enemy_rate),
+		];
+
+		self.debug_lines.truncate(lines.len());
+		for (index, line) in lines.iter().enumerate() {
+			if self.debug_lines.get(index).map_or(false, |&(ref text, _)| text == line) {
+				continue;
+			}
+
+			let sprite = phi.ttf_str_sprite(line, HUD_FONT, DEBUG_FONT_SIZE, Color::RGB(120, 255, 120)).unwrap();
+			if index < self.debug_lines.len() {
+				self.debug_lines[index] = (line.clone(), sprite);
+			} else {
+				self.debug_lines.push((line.clone(), sprite));
+			}
+		}
+
+		let (win_w, _) = phi.output_size();
+		let mut y = HUD_MARGIN;
+
+		for &(_, ref sprite) in &self.debug_lines {
+			let (w, h) = sprite.size();
+			phi.queue.sprite(Layer::Hud, 1, sprite, Rectangle {
+				x: win_w - HUD_MARGIN - w,
+				y: y,
+				w: w,
+				h: h,
+			});
+			y += h;
+		}
+	}
+
+	// Draws the current announcement in the middle of the screen, fading it out
+	fn render_banner(&self, phi: &mut Phi) {
+		if let Some((ref sprite, time_left)) = self.banner {
+			let (win_w, win_h) = phi.output_size();
+			let (w, h) = sprite.size();
+
+			phi.queue.sprite_ex(Layer::Hud, 1, sprite, Rectangle {
+				x: (win_w - w) / 2.0,
+				y: (win_h - h) / 2.0,
+				w: w,
+				h: h,
+			}, DrawParams::new().with_opacity(time_left.min(1.0)));
+		}
+	}
+
+	// Draws the boss' name and remaining health at the top of the screen
+	fn render_boss_bar(&self, phi: &mut Phi) {
+		let boss = match self.boss {
+			Some(ref boss) => boss,
+			None => return,
+		};
+
+		let (win_w, _) = phi.output_size();
+		let (label_w, label_h) = self.boss_label.size();
+
+		phi.queue.sprite(Layer::Hud, 1, &self.boss_label, Rectangle {
+			x: (win_w - label_w) / 2.0,
+			y: HUD_MARGIN,
+			w: label_w,
+			h: label_h,
+		});
+
+		let frame = Rectangle {
+			x: (win_w - BOSS_BAR_W) / 2.0,
+			y: HUD_MARGIN * 1.5 + label_h,
+			w: BOSS_BAR_W,
+			h: BOSS_BAR_H,
+		};
+
+		phi.queue.fill_rect(Layer::Hud, 0, frame, Color::RGB(40, 40, 40));
+		fill_meter(phi, frame, boss.health(), Color::RGB(200, 40, 200));
+	}
+}
+
+// Fills the left part of a HUD meter's `frame`, `fill` going from 0.0 (empty) to 1.0 (full).
+// The render queue skips fills narrower than a pixel.
+fn fill_meter(phi: &mut Phi, frame: Rectangle, fill: f64, color: Color) {
+	phi.queue.fill_rect(Layer::Hud, 1, Rectangle {
+		w: frame.w * fill.max(0.0).min(1.0),
+		..frame
+	}, color);
+}
+
+// Returns the closest of `points` to `from`, or `from` itself if there are none
+fn closest(points: &[(f64, f64)], from: (f64, f64)) -> (f64, f64) {
+	let distance = |point: (f64, f64)| (point.0 - from.0).powi(2) + (point.1 - from.1).powi(2);
+
+	points.iter().fold(None, |closest, &point| match closest {
+		Some(closest) if distance(closest) <= distance(point) => Some(closest),
+		_ => Some(point),
+	}).unwrap_or(from)
+}
+
+// Blows something up at `center`, shaking the screen more the closer it is to a player
+fn explode(phi: &mut Phi,
+           center: (f64, f64),
+           player_centers: &[(f64, f64)],
+           explosions: &mut Pool<Explosion>,
+           explosion_factory: &ExplosionFactory,
+           debris: &mut ParticleEmitter) {
+	let player_center = closest(player_centers, center);
+	let distance = ((center.0 - player_center.0).powi(2) +
+		(center.1 - player_center.1).powi(2)).sqrt();
+	if distance < EXPLOSION_SHAKE_RADIUS {
+		phi.camera.add_trauma(
+			EXPLOSION_TRAUMA * (1.0 - distance / EXPLOSION_SHAKE_RADIUS));
+	}
+
+	explosions.insert(explosion_factory.at_center(center));
+	debris.burst_at(center, DEBRIS_PER_ASTEROID);
+	phi.audio.play_sound_at(EXPLOSION_SOUND, center);
+}
+
+impl GameView {
+	// Sums up the state of the game, which peers of a network game compare to make sure
+	// that they are still playing the same one
+	pub fn checksum(&self) -> u64 {
+		let mut checksum = Checksum::new();
+
+		for player in &self.players {
+			checksum.add_f64(player.rect.x);
+			checksum.add_f64(player.rect.y);
+			checksum.add_usize(player.lives);
+			checksum.add_usize(player.score);
+			checksum.add_usize(player.weapon);
+			checksum.add_f64(player.shield);
+			checksum.add_f64(player.flash_time);
+			player.combo.add_to(&mut checksum);
+
+			for (state, &level) in player.weapon_states.iter().zip(&player.weapon_levels) {
+				state.add_to(&mut checksum);
+				checksum.add_usize(level);
+			}
+		}
+
+		for (_, asteroid) in self.asteroids.iter() {
+			checksum.add_usize(asteroid.id);
+			checksum.add_f64(asteroid.rect.x);
+			checksum.add_f64(asteroid.rect.y);
+			checksum.add_f64(asteroid.hp);
+		}
+
+		for (_, enemy) in self.enemies.iter() {
+			enemy.add_to(&mut checksum);
+		}
+
+		if let Some(ref boss) = self.boss {
+			boss.add_to(&mut checksum);
+		}
+
+		for (_, powerup) in self.powerups.iter() {
+			powerup.add_to(&mut checksum);
+		}
+
+		for (_, bullet) in self.bullets.iter() {
+			checksum.add_f64(bullet.rect().x);
+			checksum.add_f64(bullet.rect().y);
+		}
+
+		match self.mode {
+			Mode::Level(ref level) => level.add_to(&mut checksum),
+			Mode::Endless(ref difficulty) | Mode::Versus(ref difficulty) => difficulty.add_to(&mut checksum),
+		}
+
+		checksum.add_usize(self.next_id);
+		checksum.value()
+	}
+
+	// Moves the game `elapsed` seconds forward, every player doing what their `controls`
+	// say. Nothing else, besides the seed, decides what happens, so that peers of a network
+	// game that are fed the same controls play the same game.
+	pub fn update(&mut self, phi: &mut Phi, controls: &[Controls], elapsed: f64) -> ViewAction {
+		for (player, &controls) in self.players.iter_mut().zip(controls) {
+			if player.alive() {
+				player.update(controls, elapsed);
+			}
+		}
+
+		// Enemies aim at the closest ship, and sounds are heard from the middle of them
+		let player_centers: Vec<(f64, f64)> = self.players.iter()
+			.filter(|player| player.alive())
+			.map(|player| player.rect.center())
 			.collect();
 
+		let share = 1.0 / player_centers.len().max(1) as f64;
+		phi.camera.update(elapsed);
+		phi.audio.set_listener(player_centers.iter().fold((0.0, 0.0), |listener, center|
+			(listener.0 + center.0 * share, listener.1 + center.1 * share)));
+
+		// Update all the entities in place, dropping the dead ones
+		let area = AREA;
+		let rivals = self.rivals();
+		let mut targets = self.targets();
+		if rivals {
+			targets.extend(self.player_targets());
+		}
+
+		self.bullets.retain(|bullet| bullet.update(area, &targets, elapsed));
+		self.asteroids.retain(|asteroid| asteroid.update(elapsed));
+		self.explosions.retain(|explosion| explosion.update(elapsed));
+		self.powerups.retain(|powerup| powerup.update(elapsed));
+		self.popups.retain(|popup| popup.update(elapsed));
+
+		// Collect the power-ups the players touch
+		let mut collected = vec![];
+		{
+			let players = &self.players;
+			self.powerups.retain(|powerup| {
+				let collector = players.iter()
+					.find(|player| player.alive() && powerup.rect().overlaps(player.rect));
+
+				match collector {
+					Some(player) => {
+						collected.push((player.index, powerup.kind));
+						false
+					},
+					None => true,
+				}
+			});
+		}
+
+		for (index, kind) in collected {
+			phi.audio.play_sound(PICKUP_SOUND);
+			self.players[index].power_up(kind);
+
+			match kind {
+				PowerUpKind::SmartBomb => self.smart_bomb(phi, index),
+				// Brings back the first player out of lives, if one still is
+				PowerUpKind::Revive => {
+					if let Some(player) = self.players.iter_mut().find(|player| !player.alive()) {
+						player.respawn(REVIVE_LIVES);
+					}
+				},
+				_ => {},
+			}
+		}
+
+		// Enemies move, then shoot at the closest player
+		{
+			let bullets = &mut self.bullets;
+			let player_centers = &player_centers;
+
+			self.enemies.retain(|enemy| {
+				let player_center = closest(player_centers, enemy.center());
+				let alive = enemy.update(player_center, area, elapsed);
+				enemy.fire(player_center, area, elapsed, |bullet| { bullets.insert(bullet); });
+				alive
+			});
+		}
+
+		// The boss fires, calls asteroids in, and reports the parts it lost
+		let mut boss_laser = None;
+
+		if let Some(ref mut boss) = self.boss {
+			let bullets = &mut self.bullets;
+			let orders = boss.update(closest(&player_centers, boss.rect().center()), area, elapsed,
+				|bullet| { bullets.insert(bullet); });
+
+			for _ in 0..orders.summon {
+				self.next_id += 1;
+				self.asteroids.insert(self.asteroid_factory.random(self.next_id));
+			}
+
+			for center in orders.destroyed {
+				explode(phi, center, &player_centers,
+					&mut self.explosions, &self.explosion_factory, &mut self.debris);
+			}
+
+			boss_laser = boss.laser();
+		}
+
+		self.beam_sprite.add_time(elapsed);
+		self.debris.update(elapsed);
+		self.sparks.update(elapsed);
+
 		//Collision Detection
-		let mut player_alive = true;
+		let mut hits = vec![false; self.players.len()];
+
+		// The players' bullets damage the asteroids and enemies they touch, and go through them
+		// while they can pierce. Enemy bullets only hit the players, and so do the rivals' ones.
+		{
+			let players = &self.players;
+			let hits = &mut hits;
+			let last_hit = &mut self.last_hit;
+			let asteroids = &mut self.asteroids;
+			let enemies = &mut self.enemies;
+			let boss = &mut self.boss;
+			let sparks = &mut self.sparks;
+
+			self.bullets.retain(|bullet| {
+				if bullet.team() == Team::Enemy {
+					for player in players.iter().filter(|player| player.alive()) {
+						if bullet.rect().overlaps(player.rect) {
+							hits[player.index] = true;
+							return false;
+						}
+					}
+					return true;
+				}
 
-		let mut transition_bullets: Vec<_> =
-			::std::mem::replace(&mut self.bullets, vec![])
-			.into_iter()
-			.map(|bullet| MaybeAlive { alive: true, value: bullet })
-			.collect();
+				if rivals {
+					let owner = bullet.impact().owner;
+					for player in players.iter().filter(|player| player.alive() && Some(player.index) != owner) {
+						if bullet.rect().overlaps(player.rect) {
+							hits[player.index] = true;
+							return false;
+						}
+					}
+				}
+
+				let victims = asteroids.iter_mut().map(|(_, a)| (a.id, a.rect(), &mut a.hp))
+					.chain(enemies.iter_mut().map(|(_, e)| (e.id, e.rect(), &mut e.hp)))
+					.chain(boss.iter_mut().flat_map(|boss| boss.parts_mut()));
+
+				for (id, rect, hp) in victims {
+					if *hp > 0.0 && rect.overlaps(bullet.rect()) && bullet.can_hit(id) {
+						*hp -= bullet.damage();
+						sparks.burst_at(bullet.rect().center(), SPARKS_PER_IMPACT);
+
+						if let Some(owner) = bullet.impact().owner {
+							last_hit.insert(id, owner);
+						}
 
-		// Checks for asteroid collision with either a bullet or the player
-		self.asteroids =
-			::std::mem::replace(&mut self.asteroids, vec![])
-			.into_iter()
-			.filter_map(|asteroid| {
-				let mut asteroid_alive = true;
-				// Destroys any asteroids or bullets that collide
-				for bullet in &mut transition_bullets {
-					if asteroid.rect().overlaps(bullet.value.rect()) {
+						if !bullet.register_hit(id) {
+							return false;
+						}
+					}
+				}
+				true
+			});
+		}
+
+		// Checks for destroyed asteroids and enemies, and for those hitting the players.
+		// Only those shot down are worth points, to whoever last hit them.
+		let mut kills = vec![];
+		let mut drops = vec![];
+
+		{
+			let players = &self.players;
+			let player_centers = &player_centers;
+			let hits = &mut hits;
+			let last_hit = &mut self.last_hit;
+			let explosions = &mut self.explosions;
+			let explosion_factory = &self.explosion_factory;
+			let debris = &mut self.debris;
+
+			self.asteroids.retain(|asteroid| {
+				let mut asteroid_alive = asteroid.hp > 0.0;
+
+				if !asteroid_alive {
+					if let Some(killer) = last_hit.remove(&asteroid.id) {
+						kills.push((killer, asteroid.rect().center(),
+							(ASTEROID_POINTS as f64 * asteroid.rect.w / ASTEROID_SIDE) as usize));
+					}
+
+					if ::phi::rng::random::<f64>() < powerups::DROP_CHANCE {
+						drops.push(asteroid.rect().center());
+					}
+				}
+
+				// Check for collisions with the players
+				for player in players.iter().filter(|player| player.alive()) {
+					if asteroid.rect().overlaps(player.rect) {
 						asteroid_alive = false;
-						bullet.alive = false;
+						hits[player.index] = true;
 					}
 				}
-				// Check for player collision
-				if asteroid.rect().overlaps(self.player.rect) {
-					asteroid_alive = false;
-					player_alive = false;
+
+				if !asteroid_alive {
+					explode(phi, asteroid.rect().center(), player_centers,
+						explosions, explosion_factory, debris);
 				}
 
-				if asteroid_alive {
-					Some(asteroid)
-				} else {
-					self.explosions.push(
-						self.explosion_factory.at_center(
-							asteroid.rect().center()));
-					None
+				asteroid_alive
+			});
+
+			self.enemies.retain(|enemy| {
+				let mut enemy_alive = enemy.hp > 0.0;
+
+				if !enemy_alive {
+					if let Some(killer) = last_hit.remove(&enemy.id) {
+						kills.push((killer, enemy.center(), ENEMY_POINTS));
+					}
 				}
-			})
-			.collect();
 
-		self.bullets = transition_bullets.into_iter()
-			.filter_map(MaybeAlive::as_option)
-			.collect();
+				// Kamikazes, and enemies flying too close, crash into the players
+				for player in players.iter().filter(|player| player.alive()) {
+					if enemy.rect().overlaps(player.rect) {
+						enemy_alive = false;
+						hits[player.index] = true;
+					}
+				}
 
-		// Check if the player lived
-		if !player_alive {
-			self.player.lives-=1;
-			println!("Player Lives: {}", self.player.lives);
+				if !enemy_alive {
+					explode(phi, enemy.center(), player_centers,
+						explosions, explosion_factory, debris);
+				}
+
+				enemy_alive
+			});
 		}
 
-		if self.player.lives == 0 {
-			return ViewAction::ChangeView(Box::new(
-				::views::main_menu::MainMenuView::new(phi)));
+		// The boss' hull and laser hurt the players, but the boss is not harmed
+		if let Some(ref boss) = self.boss {
+			for player in self.players.iter().filter(|player| player.alive()) {
+				if boss.rect().overlaps(player.rect) ||
+					boss_laser.map_or(false, |laser| laser.hits(player.rect)) {
+					hits[player.index] = true;
+				}
+			}
+		}
+
+		// Once its core is destroyed, the boss goes down in a series of explosions
+		if self.boss.as_ref().map_or(false, Boss::defeated) {
+			let boss = self.boss.take().unwrap();
+			let hull = boss.rect();
+
+			for _ in 0..BOSS_EXPLOSIONS {
+				let center = (hull.x + ::rand::random::<f64>() * hull.w,
+					hull.y + ::rand::random::<f64>() * hull.h);
+				explode(phi, center, &player_centers,
+					&mut self.explosions, &self.explosion_factory, &mut self.debris);
+			}
+
+			phi.camera.add_trauma(BOSS_DEFEAT_TRAUMA);
+
+			if let Some(killer) = self.last_hit.remove(&boss.core_id()) {
+				kills.push((killer, hull.center(), BOSS_POINTS));
+			}
+		}
+
+		self.score_kills(phi, kills);
+
+		// While a teammate is out of lives, some of the drops can bring them back
+		let revivable = !rivals && self.players.iter().any(|player| !player.alive());
+
+		for center in drops {
+			let kind = if revivable && ::phi::rng::random::<f64>() < REVIVE_CHANCE {
+				PowerUpKind::Revive
+			} else {
+				PowerUpKind::random()
+			};
+			self.powerups.insert(PowerUp::new(kind, center));
+		}
+
+		// Check which players lived
+		for (index, &hit) in hits.iter().enumerate() {
+			if hit {
+				self.hit_player(phi, index);
+			}
+		}
+
+		// Everyone is out of lives, start again from the last checkpoint, or give up if there
+		// is none
+		if !rivals && self.players.iter().all(|player| !player.alive()) {
+			let rewound = match self.mode {
+				Mode::Level(ref mut level) => level.rewind(),
+				Mode::Endless(_) | Mode::Versus(_) => false,
+			};
+
+			if !rewound {
+				return ViewAction::ChangeView(Box::new(
+					::views::main_menu::MainMenuView::new(phi)));
+			}
+
+			self.bullets.clear();
+			self.asteroids.clear();
+			self.enemies.clear();
+			self.powerups.clear();
+			self.boss = None;
+			self.last_hit.clear();
+			for player in &mut self.players {
+				player.respawn(PLAYER_MAX_LIVES);
+			}
+			self.banner = Some((self.checkpoint_label.clone(), BANNER_DURATION));
+		}
+
+		self.forget_hits();
+
+		// Every player keeps firing as long as their fire key is held, as fast as their
+		// weapon allows
+		self.beams.clear();
+
+		for (index, controls) in controls.iter().enumerate() {
+			if !controls.fire || !self.players[index].alive() {
+				continue;
+			}
+
+			let mut targets = self.targets();
+			if rivals {
+				let id = self.players[index].id;
+				targets.extend(self.player_targets().into_iter().filter(|target| target.id != id));
+			}
+
+			let bullets = &mut self.bullets;
+			match self.players[index].try_fire(&targets, area, elapsed, |bullet| { bullets.insert(bullet); }) {
+				Some(Shot::Bullets) => {
+					phi.audio.play_sound_at(SHOOT_SOUND, self.players[index].cannons_center());
+				},
+				Some(Shot::Beam(beam)) => {
+					// Rivals burn like they would under any other fire
+					let rival = beam.hit.and_then(|id| self.players.iter().position(|player| player.id == id));
+
+					if let Some(rival) = rival {
+						self.hit_player(phi, rival);
+						self.sparks.burst_at(beam.end, 1);
+					}
+					// The target explodes during the next collision pass once its hp runs out
+					else if let Some(id) = beam.hit {
+						let damage = {
+							let player = &self.players[index];
+							player.weapons[player.weapon].damage * elapsed
+						};
+						for (_, asteroid) in self.asteroids.iter_mut().filter(|&(_, ref a)| a.id == id) {
+							asteroid.hp -= damage;
+						}
+						for (_, enemy) in self.enemies.iter_mut().filter(|&(_, ref e)| e.id == id) {
+							enemy.hp -= damage;
+						}
+						if let Some(ref mut boss) = self.boss {
+							boss.damage(id, damage);
+						}
+						self.last_hit.insert(id, index);
+						self.sparks.burst_at(beam.end, 1);
+					}
+					self.beams.push(beam);
+				},
+				None => {},
+			}
 		}
 
-		// Check if space pressed, resulting in two bullets spawned on the player
-		if phi.events.now.key_space == Some(true) {
-			self.bullets.append(&mut self.player.spawn_bullets());
+		self.spawn(elapsed);
+
+		// In versus, the last ship standing wins
+		let standing: Vec<usize> = self.players.iter()
+			.filter(|player| player.alive())
+			.map(|player| player.index)
+			.collect();
+
+		if rivals && standing.len() <= 1 && self.level_end.is_none() {
+			let result = match standing.first() {
+				Some(winner) => format!("Player {} Wins", winner + 1),
+				None => "Draw".to_string(),
+			};
+
+			self.banner = phi.ttf_str_sprite(&result, HUD_FONT, HUD_FONT_SIZE * 2, Color::RGB(220, 220, 200))
+				.map(|label| (label, LEVEL_END_DELAY));
+			self.level_end = Some(LEVEL_END_DELAY);
 		}
 
-		// Randomly create asteroids approx every % x frames
-		if ::rand::random::<usize>() % 10 == 0 {
-			self.asteroids.push(self.asteroid_factory.random(phi));
+		if let Some((_, ref mut time_left)) = self.banner {
+			*time_left -= elapsed;
+		}
+		if self.banner.as_ref().map_or(false, |&(_, time_left)| time_left <= 0.0) {
+			self.banner = None;
+		}
+
+		if let Some(ref mut time_left) = self.level_end {
+			*time_left -= elapsed;
+		}
+		if self.level_end.map_or(false, |time_left| time_left <= 0.0) {
+			return ViewAction::ChangeView(Box::new(
+				::views::main_menu::MainMenuView::new(phi)));
 		}
 
+		ViewAction::None
+	}
+
+	// Draws every entity, then the HUD over them
+	pub fn draw(&mut self, phi: &mut Phi, elapsed: f64) {
 		// Clear the screen
 		phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
 		phi.renderer.clear();
 
-		// Render the backgrounds behind the entities
-		self.bg_back.render(&mut phi.renderer, elapsed);
-		self.bg_middle.render(&mut phi.renderer, elapsed);
+		// Submit every entity, the queue sorts them by layer before drawing
+		self.bg_back.render(phi, Layer::Background, 0, elapsed);
+		self.bg_middle.render(phi, Layer::Background, 1, elapsed);
 
-		// Render all entities
-		self.player.render(phi);
+		for player in self.players.iter().filter(|player| player.alive()) {
+			player.render(phi);
+		}
 
-		for bullet in &self.bullets {
+		for (_, bullet) in self.bullets.iter() {
 			bullet.render(phi);
 		}
 
-        for asteroid in self.asteroids.iter_mut() {
+		// Stretch the beams' animation from the cannons to wherever they stop
+		for beam in &self.beams {
+			beam.render(phi, &self.beam_sprite);
+		}
+
+        for (_, asteroid) in self.asteroids.iter_mut() {
             asteroid.render(phi);
         }
 
-        for explosion in &self.explosions {
+        for (_, enemy) in self.enemies.iter() {
+            enemy.render(phi);
+        }
+
+        if let Some(ref boss) = self.boss {
+            boss.render(phi);
+
+            if let Some(laser) = boss.laser() {
+                laser.render(phi, &self.beam_sprite);
+            }
+        }
+
+        for (_, powerup) in self.powerups.iter() {
+            let letter = &self.powerup_letters.iter()
+                .find(|&&(kind, _)| kind == powerup.kind).unwrap().1;
+            powerup.render(phi, letter);
+        }
+
+        for (_, explosion) in self.explosions.iter() {
         	explosion.render(phi);
         }
 
-        // Render the foregrounds
-		self.bg_front.render(&mut phi.renderer, elapsed);
+		for (_, popup) in self.popups.iter() {
+			popup.render(phi);
+		}
+
+		self.debris.render(&mut phi.queue, Layer::Effects, 1);
+		self.sparks.render(&mut phi.queue, Layer::Effects, 2);
+
+		self.bg_front.render(phi, Layer::Foreground, 0, elapsed);
+
+		// The players' HUDs share the bottom of the screen, from left to right
+		let (win_w, _) = phi.output_size();
+		for player in &self.players {
+			let x = win_w * player.index as f64 / self.players.len() as f64;
+			player.render_hud(phi, x, &self.multiplier_labels);
+		}
+
+		self.render_boss_bar(phi);
+		self.render_banner(phi);
+		self.render_debug_overlay(phi);
+	}
+}
+
+impl View for GameView {
+
+	// Displays and updates every entity
+	fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+		// Check if the X clicked on the screen
+		if phi.events.now.quit {
+			return ViewAction::Quit;
+		}
+
+		// Goes back to the main menu if esc pressed
+		if phi.events.now.key_escape == Some(true) {
+			return ViewAction::ChangeView(Box::new(
+				::views::main_menu::MainMenuView::new(phi)));
+		}
+
+		if phi.events.now.key_f3 == Some(true) {
+			self.debug_overlay = !self.debug_overlay;
+		}
 
+		// Every player flies with their own keys, those out of lives sit the game out
+		let controls: Vec<Controls> = self.players.iter()
+			.map(|player| player.bindings.read(&phi.events))
+			.collect();
+
+		match self.update(phi, &controls, elapsed) {
+			ViewAction::None => {},
+			action => return action,
+		}
+
+		self.draw(phi, elapsed);
 		ViewAction::None
 	}
-}
\ No newline at end of file
+}
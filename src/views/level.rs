@@ -0,0 +1,271 @@
+use phi::config::{self, Section};
+use phi::net::Checksum;
+use views::enemies::{EnemyKind, EnemyWave, Formation};
+use views::patterns::Pattern;
+use std::io;
+use std::path::Path;
+
+
+/// Sizes of the asteroids spawned by level scripts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AsteroidSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl AsteroidSize {
+    pub fn from_name(name: &str) -> Option<AsteroidSize> {
+        match name {
+            "small" => Some(AsteroidSize::Small),
+            "medium" => Some(AsteroidSize::Medium),
+            "large" => Some(AsteroidSize::Large),
+            _ => None,
+        }
+    }
+
+    /// Size and hit points of the asteroid, relative to a large one.
+    pub fn scale(self) -> f64 {
+        match self {
+            AsteroidSize::Small => 0.5,
+            AsteroidSize::Medium => 0.75,
+            AsteroidSize::Large => 1.0,
+        }
+    }
+}
+
+
+/// What a level asks of the game as it plays.
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    /// Spawn an asteroid past the right edge of the screen. `y` is a fraction of the screen's
+    /// height and `speed` is in pixels per second, None meaning random.
+    Asteroid { size: AsteroidSize, y: Option<f64>, speed: Option<f64> },
+    Enemies(EnemyWave),
+    Boss,
+    /// The player reached a checkpoint, and will start from here after losing their last life.
+    Checkpoint,
+}
+
+
+/// A step of a level script.
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    /// Spawn `count` asteroids, one every `interval` seconds.
+    Asteroids { count: usize, interval: f64, size: AsteroidSize, y: Option<f64>, speed: Option<f64> },
+    Enemies(EnemyWave),
+    /// Wait `time` seconds, then until the screen is clear if `clear` is set.
+    Wait { time: f64, clear: bool },
+    Checkpoint,
+    /// Spawn the boss, then wait for the screen to be clear.
+    Boss,
+}
+
+impl Step {
+    /// Reads a step, and the seconds to wait before it starts, from a section of a level file.
+    fn from_section(section: &Section) -> Result<(f64, Step), String> {
+        let delay = section.parse_or("delay", 0.0);
+
+        // Heights are given as `random` or as a fraction of the screen's height.
+        let y = match section.get("y") {
+            None | Some("random") => None,
+            Some(_) => match section.parse::<f64>("y") {
+                Some(y) if y >= 0.0 && y <= 1.0 => Some(y),
+                _ => return Err("`y` must be `random` or between 0 and 1".to_string()),
+            },
+        };
+
+        let step = match &section.name[..] {
+            "Asteroids" => Step::Asteroids {
+                count: section.parse_or("count", 1),
+                interval: section.parse_or("interval", 0.0),
+                size: match section.get("size") {
+                    None => AsteroidSize::Large,
+                    Some(name) => try!(AsteroidSize::from_name(name)
+                        .ok_or(format!("unknown size `{}`", name))),
+                },
+                y: y,
+                speed: section.parse("speed"),
+            },
+
+            "Enemies" => {
+                let name = try!(section.get("kind").ok_or("missing `kind`".to_string()));
+                let kind = try!(EnemyKind::from_name(name).ok_or(format!("unknown kind `{}`", name)));
+                let mut wave = EnemyWave::new(kind);
+
+                match section.get("formation") {
+                    None => {},
+                    Some("none") => wave.formation = None,
+                    Some(name) => wave.formation = Some(try!(Formation::from_name(name)
+                        .ok_or(format!("unknown formation `{}`", name)))),
+                }
+
+                match section.get("pattern") {
+                    None => {},
+                    Some("none") => wave.pattern = None,
+                    Some(name) => wave.pattern = Some(try!(Pattern::named(name)
+                        .ok_or(format!("unknown pattern `{}`", name)))),
+                }
+
+                if let Some(rate) = section.parse::<f64>("fire_rate") {
+                    if rate <= 0.0 {
+                        return Err("`fire_rate` must be positive".to_string());
+                    }
+                    wave.pattern = wave.pattern.map(|pattern| pattern.with_rate(rate));
+                }
+
+                wave.count = section.parse_or("count", wave.count);
+                wave.speed = section.parse_or("speed", wave.speed);
+                wave.y = y;
+                Step::Enemies(wave)
+            },
+
+            "Wait" => Step::Wait {
+                time: section.parse_or("time", 0.0),
+                clear: match section.get("until") {
+                    None => false,
+                    Some("clear") => true,
+                    Some(other) => return Err(format!("unknown condition `{}`", other)),
+                },
+            },
+
+            "Checkpoint" => Step::Checkpoint,
+            "Boss" => Step::Boss,
+            other => return Err(format!("unknown step `{}`", other)),
+        };
+
+        Ok((delay, step))
+    }
+}
+
+
+/// A level script: waves of asteroids and enemies played one after the other.
+pub struct Level {
+    pub name: String,
+    /// Every step, along with the seconds to wait before it starts.
+    steps: Vec<(f64, Step)>,
+    current: usize,
+    /// Seconds since the current step's delay started.
+    time: f64,
+    /// Asteroids spawned, or whether the boss was, during the current step.
+    spawned: usize,
+    /// The last checkpoint reached, if any.
+    checkpoint: Option<usize>,
+}
+
+impl Level {
+    /// Loads a level file. Invalid steps are skipped with a warning.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Level> {
+        let sections = try!(config::load(path));
+        let mut name = String::new();
+        let mut steps = vec![];
+
+        for section in &sections {
+            // Lines before the first step describe the level itself
+            if section.name.is_empty() {
+                name = section.get("name").unwrap_or("").to_string();
+                continue;
+            }
+
+            match Step::from_section(section) {
+                Ok(step) => steps.push(step),
+                Err(err) => println!("Skipping step [{}]: {}", section.name, err),
+            }
+        }
+
+        // The level is only complete once everything it spawned is gone.
+        steps.push((0.0, Step::Wait { time: 0.0, clear: true }));
+
+        Ok(Level {
+            name: name,
+            steps: steps,
+            current: 0,
+            time: 0.0,
+            spawned: 0,
+            checkpoint: None,
+        })
+    }
+
+    /// Advance the script by `dt` seconds, returning what the game should spawn.
+    /// `clear` tells whether the screen is free of asteroids, enemies and bosses.
+    pub fn update(&mut self, clear: bool, dt: f64) -> Vec<Event> {
+        let mut events = vec![];
+        self.time += dt;
+
+        while self.current < self.steps.len() {
+            let (delay, step) = self.steps[self.current];
+            if self.time < delay {
+                break;
+            }
+
+            let time = self.time - delay;
+            // Whatever was spawned during this frame is not on the screen yet.
+            let clear = clear && events.is_empty();
+
+            let done = match step {
+                Step::Asteroids { count, interval, size, y, speed } => {
+                    while self.spawned < count && time >= interval * self.spawned as f64 {
+                        events.push(Event::Asteroid { size: size, y: y, speed: speed });
+                        self.spawned += 1;
+                    }
+                    self.spawned == count
+                },
+                Step::Enemies(wave) => {
+                    events.push(Event::Enemies(wave));
+                    true
+                },
+                Step::Wait { time: wait, clear: until_clear } =>
+                    time >= wait && (clear || !until_clear),
+                Step::Checkpoint => {
+                    self.checkpoint = Some(self.current);
+                    events.push(Event::Checkpoint);
+                    true
+                },
+                Step::Boss => {
+                    if self.spawned == 0 {
+                        events.push(Event::Boss);
+                        self.spawned = 1;
+                    }
+                    clear
+                },
+            };
+
+            if !done {
+                break;
+            }
+
+            self.current += 1;
+            self.time = 0.0;
+            self.spawned = 0;
+        }
+
+        events
+    }
+
+    /// Adds the current step and how far into it the level is.
+    pub fn add_to(&self, checksum: &mut Checksum) {
+        checksum.add_usize(self.current);
+        checksum.add_f64(self.time);
+        checksum.add_usize(self.spawned);
+        checksum.add_usize(self.checkpoint.map_or(0, |checkpoint| checkpoint + 1));
+    }
+
+    /// Whether every step was played and the screen cleared.
+    pub fn complete(&self) -> bool {
+        self.current == self.steps.len()
+    }
+
+    /// Starts again from the last checkpoint reached, returning false if there is none.
+    pub fn rewind(&mut self) -> bool {
+        match self.checkpoint {
+            Some(checkpoint) => {
+                // Skip the checkpoint itself, which was already announced
+                self.current = checkpoint + 1;
+                self.time = 0.0;
+                self.spawned = 0;
+                true
+            },
+            None => false,
+        }
+    }
+}
@@ -1,8 +1,25 @@
 use ::phi::{Phi, View, ViewAction};
 use ::phi::data::Rectangle;
-use ::phi::gfx::{CopySprite, Sprite};
+use ::phi::gfx::{EmitterDescr, Layer, ParticleEmitter, Sprite};
+use ::sdl2::render::BlendMode;
 use ::sdl2::pixels::Color;
-use ::views::shared::Background;
+use ::views::shared::{Background, MENU_MOVE_SOUND, MENU_SELECT_SOUND, MENU_MUSIC};
+
+const STARBURST: EmitterDescr = EmitterDescr {
+	rate: 0.0,
+	lifetime: (1.0, 2.5),
+	direction: 0.0,
+	spread: 360.0,
+	speed: (60.0, 320.0),
+	gravity: (0.0, 0.0),
+	drag: 0.3,
+	color_start: Color::RGBA(255, 255, 255, 255),
+	color_end: Color::RGBA(140, 30, 140, 0),
+	size_start: 3.0,
+	size_end: 1.0,
+	blend: BlendMode::Add,
+};
+const STARBURST_SIZE: usize = 200;
 
 struct Action {
 
@@ -29,6 +46,7 @@ impl Action {
 pub struct MainMenuView {
 	actions: Vec<Action>,
 	selected: i8,
+	starburst: ParticleEmitter,
 
 	bg_back: Background,
 	bg_middle: Background,
@@ -37,10 +55,29 @@ pub struct MainMenuView {
 
 impl MainMenuView {
 	pub fn new(phi: &mut Phi) -> MainMenuView {
+		phi.queue.set_batching(false);
+		phi.audio.play_music(MENU_MUSIC);
+
+		let (win_w, win_h) = phi.output_size();
+		let mut starburst = ParticleEmitter::new(STARBURST, STARBURST_SIZE);
+		starburst.burst_at((win_w / 2.0, win_h / 2.0), STARBURST_SIZE);
+
 		MainMenuView {
 			actions: vec![
 				Action::new(phi, "New Game", Box::new(|phi| {
-					ViewAction::ChangeView(Box::new(::views::game::GameView::new(phi)))
+					ViewAction::ChangeView(Box::new(::views::game::GameView::new(phi, 1)))
+				})),
+				Action::new(phi, "2 Players", Box::new(|phi| {
+					ViewAction::ChangeView(Box::new(::views::game::GameView::new(phi, 2)))
+				})),
+				Action::new(phi, "Endless", Box::new(|phi| {
+					ViewAction::ChangeView(Box::new(::views::game::GameView::endless(phi, 1)))
+				})),
+				Action::new(phi, "Versus", Box::new(|phi| {
+					ViewAction::ChangeView(Box::new(::views::game::GameView::versus(phi)))
+				})),
+				Action::new(phi, "Options", Box::new(|phi| {
+					ViewAction::ChangeView(Box::new(::views::options::OptionsView::new(phi)))
 				})),
 				Action::new(phi, "Quit", Box::new(|_| {
 					ViewAction::Quit
@@ -48,20 +85,24 @@ impl MainMenuView {
 			],
 
 			selected: 0,
+			starburst: starburst,
 
             bg_back: Background {
                 pos: 0.0,
                 vel: 20.0,
+                parallax: 0.25,
                 sprite: Sprite::load(&mut phi.renderer, "assets/starBG.png").unwrap(),
             },
             bg_middle: Background {
                 pos: 0.0,
                 vel: 40.0,
+                parallax: 0.5,
                 sprite: Sprite::load(&mut phi.renderer, "assets/starMG.png").unwrap(),
             },
             bg_front: Background {
                 pos: 0.0,
                 vel: 80.0,
+                parallax: 1.0,
                 sprite: Sprite::load(&mut phi.renderer, "assets/starFG.png").unwrap(),
             },
 		}
@@ -75,10 +116,12 @@ impl View for MainMenuView {
 		}
 
 		if phi.events.now.key_space == Some(true) {
+			phi.audio.play_sound(MENU_SELECT_SOUND);
 			return (self.actions[self.selected as usize].func)(phi);
 		}
 
 		if phi.events.now.key_up == Some(true) {
+			phi.audio.play_sound(MENU_MOVE_SOUND);
 			self.selected -= 1;
 			if self.selected < 0 {
 				self.selected = self.actions.len() as i8 - 1;
@@ -86,6 +129,7 @@ impl View for MainMenuView {
 		}
 
 		if phi.events.now.key_down == Some(true) {
+			phi.audio.play_sound(MENU_MOVE_SOUND);
 			self.selected += 1;
 			if self.selected >= self.actions.len() as i8 {
 				self.selected = 0;
@@ -95,9 +139,12 @@ impl View for MainMenuView {
 		phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
 		phi.renderer.clear();
 
-		self.bg_back.render(&mut phi.renderer, elapsed);
-		self.bg_middle.render(&mut phi.renderer, elapsed);
-		self.bg_front.render(&mut phi.renderer, elapsed);
+		self.bg_back.render(phi, Layer::Background, 0, elapsed);
+		self.bg_middle.render(phi, Layer::Background, 1, elapsed);
+		self.bg_front.render(phi, Layer::Foreground, 0, elapsed);
+
+		self.starburst.update(elapsed);
+		self.starburst.render(&mut phi.queue, Layer::Foreground, 1);
 
 		let (win_w, win_h) = phi.output_size();
 		let label_h = 50.0;
@@ -106,26 +153,24 @@ impl View for MainMenuView {
 		let box_h = self.actions.len() as f64 * label_h;
 		let margin_h = 10.0;
 
-		phi.renderer.set_draw_color(Color::RGB(70, 15, 70));
-		phi.renderer.fill_rect(Rectangle {
+		phi.queue.fill_rect(Layer::Hud, 0, Rectangle {
 			w: box_w + border_width * 2.0,
 			h: box_h + border_width * 2.0 + margin_h * 2.0,
 			x: (win_w - box_w) / 2.0 - border_width,
 			y: (win_h - box_h) / 2.0 - margin_h - border_width,
-		}.to_sdl().unwrap());
+		}, Color::RGB(70, 15, 70));
 
-		phi.renderer.set_draw_color(Color::RGB(140, 30, 140));
-		phi.renderer.fill_rect(Rectangle {
+		phi.queue.fill_rect(Layer::Hud, 1, Rectangle {
 			w: box_w,
 			h: box_h + margin_h * 2.0,
 			x: (win_w - box_w) / 2.0,
 			y: (win_h - box_h) / 2.0 - margin_h,
-		}.to_sdl().unwrap());
+		}, Color::RGB(140, 30, 140));
 
 		for (i, action) in self.actions.iter().enumerate() {
 			if self.selected as usize == i {
 				let (w, h) = action.hover_sprite.size();
-				phi.renderer.copy_sprite(&action.hover_sprite, Rectangle {
+				phi.queue.sprite(Layer::Hud, 2, &action.hover_sprite, Rectangle {
 					x: (win_w - w) / 2.0,
 					y: (win_h - box_h + label_h - h) / 2.0 + label_h * i as f64,
 					w: w,
@@ -133,7 +178,7 @@ impl View for MainMenuView {
 				});
 			} else {
 				let (w, h) = action.idle_sprite.size();
-                phi.renderer.copy_sprite(&action.idle_sprite, Rectangle {
+                phi.queue.sprite(Layer::Hud, 2, &action.idle_sprite, Rectangle {
                     x: (win_w - w) / 2.0,
                     y: (win_h - box_h + label_h - h) / 2.0 + label_h * i as f64,
                     w: w,
@@ -1,4 +1,15 @@
+pub mod boss;
+pub mod controls;
+pub mod difficulty;
 pub mod game;
+pub mod level;
 pub mod main_menu;
+pub mod netplay;
+pub mod options;
+pub mod patterns;
+pub mod powerups;
+pub mod score;
 pub mod shared;
-pub mod bullets;
\ No newline at end of file
+pub mod bullets;
+pub mod enemies;
+pub mod trajectory;
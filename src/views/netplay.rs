@@ -0,0 +1,126 @@
+use ::phi::{Phi, View, ViewAction};
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Layer, Sprite};
+use ::phi::net::{Lockstep, Status};
+use ::sdl2::pixels::Color;
+use ::views::controls::{Bindings, Controls};
+use ::views::game::GameView;
+
+// Seconds of game simulated by every frame, whatever the frame rates of the peers
+const STEP: f64 = 0.01;
+// Frames simulated at most in one go, to catch up after waiting for the peer
+const MAX_STEPS: usize = 4;
+
+const FONT: &'static str = "assets/belligerent.ttf";
+const FONT_SIZE: i32 = 24;
+
+// A co-op game with a remote player, both peers simulating it from the inputs of both
+// players. The local player always flies with the arrows.
+pub struct NetplayView {
+	lockstep: Lockstep,
+	// Starts once connected, the host having picked the seed
+	game: Option<GameView>,
+	// Time not simulated yet
+	lag: f64,
+
+	// Tells what the connection is up to, unless the game runs fine
+	status: Status,
+	status_label: Option<Sprite>,
+}
+
+impl NetplayView {
+	pub fn new(phi: &mut Phi, lockstep: Lockstep) -> NetplayView {
+		let status = lockstep.status();
+		let status_label = NetplayView::status_label(phi, &lockstep);
+
+		NetplayView {
+			lockstep: lockstep,
+			game: None,
+			lag: 0.0,
+			status: status,
+			status_label: status_label,
+		}
+	}
+
+	fn status_label(phi: &mut Phi, lockstep: &Lockstep) -> Option<Sprite> {
+		let text = match lockstep.status() {
+			Status::Connecting if lockstep.player() == 0 => "Waiting for the other player".to_string(),
+			Status::Connecting => "Joining the game".to_string(),
+			Status::Running => return None,
+			Status::Desynced(frame) => format!("Out of sync since frame {}", frame),
+			Status::Disconnected => "The other player left".to_string(),
+		};
+
+		phi.ttf_str_sprite(&text, FONT, FONT_SIZE, Color::RGB(220, 220, 200))
+	}
+}
+
+impl View for NetplayView {
+	fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+		if phi.events.now.quit {
+			return ViewAction::Quit;
+		}
+
+		if phi.events.now.key_escape == Some(true) {
+			return ViewAction::ChangeView(Box::new(
+				::views::main_menu::MainMenuView::new(phi)));
+		}
+
+		self.lockstep.poll();
+
+		if self.game.is_none() && self.lockstep.status() == Status::Running {
+			self.game = Some(GameView::networked(phi, self.lockstep.seed()));
+		}
+
+		match self.game {
+			Some(ref mut game) => {
+				// Frames are only simulated once the peer's inputs for them arrived, the
+				// game freezing in the meantime
+				self.lag = (self.lag + elapsed).min(STEP * MAX_STEPS as f64);
+
+				while self.lag >= STEP {
+					self.lockstep.push_input(Bindings::Arrows.read(&phi.events).to_bits());
+
+					let inputs = match self.lockstep.next_inputs() {
+						Some(inputs) => inputs,
+						None => break,
+					};
+
+					let controls = [Controls::from_bits(inputs[0]), Controls::from_bits(inputs[1])];
+					match game.update(phi, &controls, STEP) {
+						ViewAction::None => {},
+						action => return action,
+					}
+
+					self.lockstep.advance(game.checksum());
+					self.lag -= STEP;
+				}
+
+				game.draw(phi, elapsed);
+			},
+			None => {
+				phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+				phi.renderer.clear();
+			},
+		}
+
+		if self.lockstep.status() != self.status {
+			self.status = self.lockstep.status();
+			self.status_label = NetplayView::status_label(phi, &self.lockstep);
+		}
+
+		if let Some(ref label) = self.status_label {
+			let (win_w, win_h) = phi.output_size();
+			let (label_w, label_h) = label.size();
+
+			phi.queue.sprite(Layer::Hud, 10, label, Rectangle {
+				x: (win_w - label_w) / 2.0,
+				y: (win_h - label_h) / 2.0,
+				w: label_w,
+				h: label_h,
+			});
+		}
+
+		ViewAction::None
+	}
+}
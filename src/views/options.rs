@@ -0,0 +1,240 @@
+use ::phi::{Phi, View, ViewAction};
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Layer, Sprite};
+use ::phi::settings::{Settings, WindowMode};
+use ::sdl2::pixels::Color;
+use ::views::shared::{Background, MENU_MOVE_SOUND, MENU_SELECT_SOUND, MENU_MUSIC};
+
+const WINDOW_MODES: [WindowMode; 3] = [
+	WindowMode::Windowed,
+	WindowMode::Borderless,
+	WindowMode::Fullscreen,
+];
+
+const RESOLUTIONS: [(u32, u32); 6] = [
+	(800, 600),
+	(1024, 768),
+	(1280, 720),
+	(1280, 960),
+	(1600, 900),
+	(1920, 1080),
+];
+
+// 0 means that the frame rate is not limited
+const FPS_CAPS: [u32; 5] = [30, 60, 120, 144, 0];
+
+// Percentage added or removed from a volume at every key press
+const VOLUME_STEP: isize = 10;
+
+// Rows of the options menu, in display order
+#[derive(Clone, Copy, PartialEq)]
+enum OptionRow {
+	WindowMode,
+	Resolution,
+	VSync,
+	FpsCap,
+	MasterVolume,
+	MusicVolume,
+	SfxVolume,
+	Back,
+}
+
+const ROWS: [OptionRow; 8] = [
+	OptionRow::WindowMode,
+	OptionRow::Resolution,
+	OptionRow::VSync,
+	OptionRow::FpsCap,
+	OptionRow::MasterVolume,
+	OptionRow::MusicVolume,
+	OptionRow::SfxVolume,
+	OptionRow::Back,
+];
+
+// Returns the element after (or before, if step is negative) current, wrapping around
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, step: isize) -> T {
+	let len = values.len() as isize;
+	let index = values.iter().position(|&v| v == current).unwrap_or(0) as isize;
+	values[((index + step) % len + len) as usize % values.len()]
+}
+
+// Moves a volume percentage by `step` increments, staying between 0 and 100
+fn nudge_volume(volume: u32, step: isize) -> u32 {
+	(volume as isize + step * VOLUME_STEP).max(0).min(100) as u32
+}
+
+pub struct OptionsView {
+	settings: Settings,
+	selected: usize,
+
+	// Idle and hover sprites of every row, regenerated when a value changes
+	labels: Vec<(Sprite, Sprite)>,
+
+	bg_back: Background,
+	bg_middle: Background,
+	bg_front: Background,
+}
+
+impl OptionsView {
+	pub fn new(phi: &mut Phi) -> OptionsView {
+		let settings = phi.settings();
+		phi.audio.play_music(MENU_MUSIC);
+
+		OptionsView {
+			settings: settings,
+			selected: 0,
+			labels: OptionsView::labels(phi, settings),
+
+			bg_back: Background {
+				pos: 0.0,
+				vel: 20.0,
+				parallax: 0.25,
+				sprite: Sprite::load(&mut phi.renderer, "assets/starBG.png").unwrap(),
+			},
+			bg_middle: Background {
+				pos: 0.0,
+				vel: 40.0,
+				parallax: 0.5,
+				sprite: Sprite::load(&mut phi.renderer, "assets/starMG.png").unwrap(),
+			},
+			bg_front: Background {
+				pos: 0.0,
+				vel: 80.0,
+				parallax: 1.0,
+				sprite: Sprite::load(&mut phi.renderer, "assets/starFG.png").unwrap(),
+			},
+		}
+	}
+
+	fn label(settings: Settings, row: OptionRow) -> String {
+		match row {
+			OptionRow::WindowMode => format!("Display: {}", match settings.window_mode {
+				WindowMode::Windowed => "Windowed",
+				WindowMode::Borderless => "Borderless",
+				WindowMode::Fullscreen => "Fullscreen",
+			}),
+			OptionRow::Resolution =>
+				format!("Resolution: {}x{}", settings.resolution.0, settings.resolution.1),
+			OptionRow::VSync =>
+				format!("VSync: {}", if settings.vsync { "On" } else { "Off" }),
+			OptionRow::FpsCap =>
+				if settings.fps_cap == 0 {
+					"FPS Cap: None".to_string()
+				} else {
+					format!("FPS Cap: {}", settings.fps_cap)
+				},
+			OptionRow::MasterVolume => format!("Master Volume: {}%", settings.master_volume),
+			OptionRow::MusicVolume => format!("Music Volume: {}%", settings.music_volume),
+			OptionRow::SfxVolume => format!("Effects Volume: {}%", settings.sfx_volume),
+			OptionRow::Back => "Back".to_string(),
+		}
+	}
+
+	fn labels(phi: &mut Phi, settings: Settings) -> Vec<(Sprite, Sprite)> {
+		ROWS.iter().map(|&row| {
+			let label = OptionsView::label(settings, row);
+			(phi.ttf_str_sprite(&label, "assets/belligerent.ttf", 32, Color::RGB(220, 220, 200)).unwrap(),
+			 phi.ttf_str_sprite(&label, "assets/belligerent.ttf", 38, Color::RGB(255, 255, 255)).unwrap())
+		}).collect()
+	}
+
+	// Changes the value of the selected row and applies it right away
+	fn change(&mut self, phi: &mut Phi, step: isize) {
+		match ROWS[self.selected] {
+			OptionRow::WindowMode =>
+				self.settings.window_mode = cycle(&WINDOW_MODES, self.settings.window_mode, step),
+			OptionRow::Resolution =>
+				self.settings.resolution = cycle(&RESOLUTIONS, self.settings.resolution, step),
+			OptionRow::VSync =>
+				self.settings.vsync = !self.settings.vsync,
+			OptionRow::FpsCap =>
+				self.settings.fps_cap = cycle(&FPS_CAPS, self.settings.fps_cap, step),
+			OptionRow::MasterVolume =>
+				self.settings.master_volume = nudge_volume(self.settings.master_volume, step),
+			OptionRow::MusicVolume =>
+				self.settings.music_volume = nudge_volume(self.settings.music_volume, step),
+			OptionRow::SfxVolume =>
+				self.settings.sfx_volume = nudge_volume(self.settings.sfx_volume, step),
+			OptionRow::Back =>
+				return,
+		}
+
+		phi.apply_settings(self.settings);
+		phi.audio.play_sound(MENU_SELECT_SOUND);
+		self.labels = OptionsView::labels(phi, self.settings);
+	}
+}
+
+impl View for OptionsView {
+	fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+		if phi.events.now.quit {
+			return ViewAction::Quit;
+		}
+
+		if phi.events.now.key_escape == Some(true) ||
+			(phi.events.now.key_space == Some(true) && ROWS[self.selected] == OptionRow::Back) {
+			phi.audio.play_sound(MENU_SELECT_SOUND);
+			return ViewAction::ChangeView(Box::new(
+				::views::main_menu::MainMenuView::new(phi)));
+		}
+
+		if phi.events.now.key_up == Some(true) {
+			self.selected = (self.selected + ROWS.len() - 1) % ROWS.len();
+			phi.audio.play_sound(MENU_MOVE_SOUND);
+		}
+
+		if phi.events.now.key_down == Some(true) {
+			self.selected = (self.selected + 1) % ROWS.len();
+			phi.audio.play_sound(MENU_MOVE_SOUND);
+		}
+
+		if phi.events.now.key_left == Some(true) {
+			self.change(phi, -1);
+		}
+
+		if phi.events.now.key_right == Some(true) || phi.events.now.key_space == Some(true) {
+			self.change(phi, 1);
+		}
+
+		phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+		phi.renderer.clear();
+
+		self.bg_back.render(phi, Layer::Background, 0, elapsed);
+		self.bg_middle.render(phi, Layer::Background, 1, elapsed);
+		self.bg_front.render(phi, Layer::Foreground, 0, elapsed);
+
+		let (win_w, win_h) = phi.output_size();
+		let label_h = 50.0;
+		let border_width = 3.0;
+		let box_w = 520.0;
+		let box_h = self.labels.len() as f64 * label_h;
+		let margin_h = 10.0;
+
+		phi.queue.fill_rect(Layer::Hud, 0, Rectangle {
+			w: box_w + border_width * 2.0,
+			h: box_h + border_width * 2.0 + margin_h * 2.0,
+			x: (win_w - box_w) / 2.0 - border_width,
+			y: (win_h - box_h) / 2.0 - margin_h - border_width,
+		}, Color::RGB(70, 15, 70));
+
+		phi.queue.fill_rect(Layer::Hud, 1, Rectangle {
+			w: box_w,
+			h: box_h + margin_h * 2.0,
+			x: (win_w - box_w) / 2.0,
+			y: (win_h - box_h) / 2.0 - margin_h,
+		}, Color::RGB(140, 30, 140));
+
+		for (i, &(ref idle, ref hover)) in self.labels.iter().enumerate() {
+			let sprite = if self.selected == i { hover } else { idle };
+			let (w, h) = sprite.size();
+
+			phi.queue.sprite(Layer::Hud, 2, sprite, Rectangle {
+				x: (win_w - w) / 2.0,
+				y: (win_h - box_h + label_h - h) / 2.0 + label_h * i as f64,
+				w: w,
+				h: h,
+			});
+		}
+
+		ViewAction::None
+	}
+}
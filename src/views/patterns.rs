@@ -0,0 +1,221 @@
+use phi::net::Checksum;
+use views::bullets::{fired_bullet, BulletKind, Team};
+
+
+/// A bullet-hell pattern: volleys of bullets fired on a schedule.
+/// Rings, spirals, aimed fans and rotating streams are all described by the same few numbers.
+#[derive(Clone, Copy, Debug)]
+pub struct Pattern {
+    /// Bullets in every volley.
+    pub count: usize,
+    /// Angle, in degrees, between two neighbouring bullets of a volley.
+    pub angle_step: f64,
+    /// Direction, in degrees, of the middle of the first volley. 0 is right, 90 is down.
+    pub angle: f64,
+    /// Whether volleys are centered on the player's direction rather than on `angle`.
+    pub aimed: bool,
+    /// Degrees the pattern turns by every second.
+    pub rotation_speed: f64,
+    /// If positive, the pattern turns back and forth within this many degrees on either side
+    /// of its direction instead of turning round.
+    pub sweep: f64,
+    /// Pixels traveled by the bullets every second.
+    pub speed: f64,
+    /// Seconds between two volleys.
+    pub interval: f64,
+    pub damage: f64,
+}
+
+/// Evenly spaced bullets in every direction.
+pub const RING: Pattern = Pattern {
+    count: 16,
+    angle_step: 360.0 / 16.0,
+    angle: 180.0,
+    aimed: false,
+    rotation_speed: 0.0,
+    sweep: 0.0,
+    speed: 150.0,
+    interval: 1.2,
+    damage: 1.0,
+};
+
+/// A few arms turning quickly, leaving spiraling trails of bullets.
+pub const SPIRAL: Pattern = Pattern {
+    count: 4,
+    angle_step: 90.0,
+    angle: 180.0,
+    aimed: false,
+    rotation_speed: 120.0,
+    sweep: 0.0,
+    speed: 140.0,
+    interval: 0.12,
+    damage: 1.0,
+};
+
+/// A tight fan fired at the player.
+pub const AIMED_FAN: Pattern = Pattern {
+    count: 5,
+    angle_step: 12.0,
+    angle: 180.0,
+    aimed: true,
+    rotation_speed: 0.0,
+    sweep: 0.0,
+    speed: 200.0,
+    interval: 1.5,
+    damage: 1.0,
+};
+
+/// A single stream of bullets which sweeps back and forth in front of the shooter.
+pub const ROTATING_STREAM: Pattern = Pattern {
+    count: 1,
+    angle_step: 0.0,
+    angle: 180.0,
+    aimed: false,
+    rotation_speed: 90.0,
+    sweep: 40.0,
+    speed: 220.0,
+    interval: 0.08,
+    damage: 1.0,
+};
+
+/// A wide, slowly wobbling fan, used by the boss' turrets.
+pub const CURTAIN: Pattern = Pattern {
+    count: 7,
+    angle_step: 70.0 / 6.0,
+    angle: 180.0,
+    aimed: false,
+    rotation_speed: 30.0,
+    sweep: 15.0,
+    speed: 180.0,
+    interval: 0.9,
+    damage: 1.0,
+};
+
+/// One bullet at a time, straight at the player.
+pub const AIMED_SHOT: Pattern = Pattern {
+    count: 1,
+    angle_step: 0.0,
+    angle: 180.0,
+    aimed: true,
+    rotation_speed: 0.0,
+    sweep: 0.0,
+    speed: 220.0,
+    interval: 1.5,
+    damage: 1.0,
+};
+
+impl Pattern {
+    /// The pattern called `name` in level scripts, e.g. `aimed_fan`.
+    pub fn named(name: &str) -> Option<Pattern> {
+        match name {
+            "ring" => Some(RING),
+            "spiral" => Some(SPIRAL),
+            "aimed_fan" => Some(AIMED_FAN),
+            "rotating_stream" => Some(ROTATING_STREAM),
+            "curtain" => Some(CURTAIN),
+            "aimed_shot" => Some(AIMED_SHOT),
+            _ => None,
+        }
+    }
+
+    /// The same pattern, firing `rate` volleys per second.
+    pub fn with_rate(self, rate: f64) -> Pattern {
+        Pattern { interval: 1.0 / rate, ..self }
+    }
+
+    /// Current rotation of the pattern, in degrees, `time` seconds after it started.
+    fn rotation(&self, time: f64) -> f64 {
+        let turned = self.rotation_speed * time;
+
+        if self.sweep > 0.0 {
+            // Triangle wave between -sweep and sweep.
+            let phase = positive_rem(turned / (4.0 * self.sweep) + 0.25, 1.0);
+            self.sweep * if phase < 0.5 { 4.0 * phase - 1.0 } else { 3.0 - 4.0 * phase }
+        } else {
+            turned
+        }
+    }
+}
+
+
+/// Fires a pattern's volleys as time goes by.
+pub struct PatternEmitter {
+    pub pattern: Pattern,
+    /// Time left before the next volley.
+    cooldown: f64,
+    /// Seconds since the emitter started firing, which determines the pattern's rotation.
+    time: f64,
+}
+
+impl PatternEmitter {
+    pub fn new(pattern: Pattern) -> PatternEmitter {
+        PatternEmitter {
+            pattern: pattern,
+            cooldown: 0.0,
+            time: 0.0,
+        }
+    }
+
+    /// Wait `delay` seconds before the first volley.
+    pub fn delayed(self, delay: f64) -> PatternEmitter {
+        PatternEmitter { cooldown: delay, ..self }
+    }
+
+    /// Replace the pattern, keeping the emitter's timing.
+    pub fn set_pattern(&mut self, pattern: Pattern) {
+        self.pattern = pattern;
+    }
+
+    /// Adds the timers, which decide when the next volley comes and where it goes.
+    pub fn add_to(&self, checksum: &mut Checksum) {
+        checksum.add_f64(self.cooldown);
+        checksum.add_f64(self.time);
+    }
+
+    /// Advance the emitter by `dt` seconds, handing every bullet it fires to `fire`.
+    /// Volleys are fired from `origin`, aimed patterns towards `player`.
+    pub fn update<F>(&mut self, origin: (f64, f64), player: (f64, f64), team: Team, dt: f64, mut fire: F)
+        where F: FnMut(BulletKind)
+    {
+        if self.pattern.interval <= 0.0 {
+            return;
+        }
+
+        self.cooldown -= dt;
+
+        // At low frame rates, several volleys may be due within the same frame.
+        while self.cooldown <= 0.0 {
+            self.cooldown += self.pattern.interval;
+            self.volley(origin, player, team, &mut fire);
+        }
+
+        self.time += dt;
+    }
+
+    fn volley<F>(&self, origin: (f64, f64), player: (f64, f64), team: Team, fire: &mut F)
+        where F: FnMut(BulletKind)
+    {
+        let pattern = &self.pattern;
+
+        let center = if pattern.aimed {
+            (player.1 - origin.1).atan2(player.0 - origin.0).to_degrees()
+        } else {
+            pattern.angle
+        } + pattern.rotation(self.time);
+
+        // Spread the bullets evenly on both sides of the volley's center.
+        let first = center - pattern.angle_step * (pattern.count as f64 - 1.0) / 2.0;
+
+        for i in 0..pattern.count {
+            let angle = (first + pattern.angle_step * i as f64).to_radians();
+            fire(fired_bullet(team, origin, angle, pattern.speed, pattern.damage));
+        }
+    }
+}
+
+
+/// Remainder of `x / m` which is never negative, unlike `%`.
+fn positive_rem(x: f64, m: f64) -> f64 {
+    let r = x % m;
+    if r < 0.0 { r + m } else { r }
+}
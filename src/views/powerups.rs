@@ -0,0 +1,145 @@
+use phi::Phi;
+use phi::data::Rectangle;
+use phi::gfx::{DrawParams, Layer, Sprite};
+use phi::net::Checksum;
+use sdl2::pixels::Color;
+
+
+pub const POWERUP_SIZE: f64 = 24.0;
+/// Power-ups are drawn with the bullets, between the player and asteroids.
+pub const POWERUP_Z: i32 = 1;
+/// Chance for a destroyed asteroid to leave a power-up behind.
+pub const DROP_CHANCE: f64 = 0.12;
+
+/// Pixels traveled to the left every second.
+const DRIFT_SPEED: f64 = 60.0;
+/// Pixels a power-up bobs up and down by, and how fast, in radians per second.
+const BOB_AMPLITUDE: f64 = 6.0;
+const BOB_SPEED: f64 = 4.0;
+
+
+/// What a power-up does once the player collects it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PowerUpKind {
+    /// Absorbs the next hit, for a while.
+    Shield,
+    /// One more life, up to a cap.
+    ExtraLife,
+    /// Adds barrels or fire rate to the weapon in use.
+    WeaponUp,
+    /// Destroys every asteroid, enemy and enemy bullet on the screen.
+    SmartBomb,
+    /// Brings back a player who lost all their lives, only dropped while one has.
+    Revive,
+}
+
+/// Kinds which may drop at any time.
+const DROPS: [PowerUpKind; 4] =
+    [PowerUpKind::Shield, PowerUpKind::ExtraLife, PowerUpKind::WeaponUp, PowerUpKind::SmartBomb];
+
+impl PowerUpKind {
+    pub fn all() -> [PowerUpKind; 5] {
+        [PowerUpKind::Shield, PowerUpKind::ExtraLife, PowerUpKind::WeaponUp,
+         PowerUpKind::SmartBomb, PowerUpKind::Revive]
+    }
+
+    /// Relative chances for a drop to be of each kind, the strongest being the rarest.
+    fn weight(self) -> f64 {
+        match self {
+            PowerUpKind::Shield => 4.0,
+            PowerUpKind::ExtraLife => 1.0,
+            PowerUpKind::WeaponUp => 4.0,
+            PowerUpKind::SmartBomb => 1.5,
+            PowerUpKind::Revive => 0.0,
+        }
+    }
+
+    /// A kind which may drop at any time, picked according to its weight.
+    pub fn random() -> PowerUpKind {
+        let kinds = DROPS;
+        let total = kinds.iter().fold(0.0, |sum, kind| sum + kind.weight());
+        let mut pick = ::phi::rng::random::<f64>() * total;
+
+        for &kind in &kinds {
+            if pick < kind.weight() {
+                return kind;
+            }
+            pick -= kind.weight();
+        }
+        kinds[kinds.len() - 1]
+    }
+
+    /// Letter drawn on top of the power-up.
+    pub fn letter(self) -> &'static str {
+        match self {
+            PowerUpKind::Shield => "S",
+            PowerUpKind::ExtraLife => "1",
+            PowerUpKind::WeaponUp => "W",
+            PowerUpKind::SmartBomb => "B",
+            PowerUpKind::Revive => "R",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PowerUpKind::Shield => Color::RGB(80, 200, 255),
+            PowerUpKind::ExtraLife => Color::RGB(80, 230, 100),
+            PowerUpKind::WeaponUp => Color::RGB(255, 200, 60),
+            PowerUpKind::SmartBomb => Color::RGB(255, 70, 70),
+            PowerUpKind::Revive => Color::RGB(230, 110, 255),
+        }
+    }
+}
+
+
+/// A pickup drifting to the left, collected when the player touches it.
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    center: (f64, f64),
+    /// Seconds since it was dropped.
+    time: f64,
+}
+
+impl PowerUp {
+    pub fn new(kind: PowerUpKind, center: (f64, f64)) -> PowerUp {
+        PowerUp {
+            kind: kind,
+            center: center,
+            time: 0.0,
+        }
+    }
+
+    /// Return whether the power-up is still on screen.
+    pub fn update(&mut self, dt: f64) -> bool {
+        self.time += dt;
+        self.center.0 -= DRIFT_SPEED * dt;
+        self.center.0 > -POWERUP_SIZE
+    }
+
+    pub fn add_to(&self, checksum: &mut Checksum) {
+        checksum.add_usize(self.kind as usize);
+        checksum.add_f64(self.center.0);
+        checksum.add_f64(self.center.1);
+        checksum.add_f64(self.time);
+    }
+
+    pub fn rect(&self) -> Rectangle {
+        let bob = BOB_AMPLITUDE * (self.time * BOB_SPEED).sin();
+        Rectangle::with_size(POWERUP_SIZE, POWERUP_SIZE).center_at((self.center.0, self.center.1 + bob))
+    }
+
+    /// Draws a pulsing box of the kind's color with `letter` on top of it.
+    pub fn render(&self, phi: &mut Phi, letter: &Sprite) {
+        let rect = self.rect();
+        let pulse = 0.6 + 0.4 * (self.time * BOB_SPEED * 2.0).sin().abs();
+
+        phi.queue.fill_rect(Layer::Entities, POWERUP_Z, rect, Color::RGB(255, 255, 255));
+        phi.queue.fill_rect(Layer::Entities, POWERUP_Z, Rectangle::with_size(rect.w - 4.0, rect.h - 4.0)
+            .center_at(rect.center()), self.kind.color());
+
+        let (w, h) = letter.size();
+        phi.queue.sprite_ex(Layer::Entities, POWERUP_Z,
+            letter, Rectangle::with_size(w, h).center_at(rect.center()),
+            DrawParams::new().with_opacity(pulse));
+    }
+}
@@ -0,0 +1,106 @@
+use phi::Phi;
+use phi::data::Rectangle;
+use phi::gfx::{DrawParams, Layer, Sprite};
+use phi::net::Checksum;
+
+
+/// Kills needed to raise the multiplier by one.
+const COMBO_STEP: usize = 5;
+pub const MAX_MULTIPLIER: usize = 8;
+/// Seconds without a kill after which the multiplier drops by one.
+pub const COMBO_TIMEOUT: f64 = 2.0;
+
+/// Seconds a popup stays on screen, and pixels it rises by every second.
+const POPUP_DURATION: f64 = 0.8;
+const POPUP_RISE: f64 = 50.0;
+
+
+/// Counts kills made in quick succession, which multiply the points they are worth.
+pub struct Combo {
+    /// Kills since the combo started.
+    kills: usize,
+    /// Time left before the multiplier drops.
+    timer: f64,
+}
+
+impl Combo {
+    pub fn new() -> Combo {
+        Combo {
+            kills: 0,
+            timer: 0.0,
+        }
+    }
+
+    pub fn multiplier(&self) -> usize {
+        ::std::cmp::min(1 + self.kills / COMBO_STEP, MAX_MULTIPLIER)
+    }
+
+    /// Fraction of the timeout left before the multiplier drops.
+    pub fn time_left(&self) -> f64 {
+        self.timer / COMBO_TIMEOUT
+    }
+
+    /// Counts a kill, returning the points it is worth once multiplied.
+    pub fn kill(&mut self, points: usize) -> usize {
+        self.kills += 1;
+        self.timer = COMBO_TIMEOUT;
+        points * self.multiplier()
+    }
+
+    /// The multiplier drops one step at a time when the player stops killing.
+    pub fn update(&mut self, dt: f64) {
+        if self.kills == 0 {
+            return;
+        }
+
+        self.timer -= dt;
+        if self.timer <= 0.0 {
+            let multiplier = self.multiplier();
+            self.kills = if multiplier > 1 { (multiplier - 2) * COMBO_STEP } else { 0 };
+            self.timer = if self.kills > 0 { COMBO_TIMEOUT } else { 0.0 };
+        }
+    }
+
+    /// Getting hit breaks the combo.
+    pub fn reset(&mut self) {
+        self.kills = 0;
+        self.timer = 0.0;
+    }
+
+    pub fn add_to(&self, checksum: &mut Checksum) {
+        checksum.add_usize(self.kills);
+        checksum.add_f64(self.timer);
+    }
+}
+
+
+/// Points earned by a kill, floating up from where it happened.
+pub struct ScorePopup {
+    label: Sprite,
+    center: (f64, f64),
+    time: f64,
+}
+
+impl ScorePopup {
+    pub fn new(label: Sprite, center: (f64, f64)) -> ScorePopup {
+        ScorePopup {
+            label: label,
+            center: center,
+            time: 0.0,
+        }
+    }
+
+    /// Return false once the popup has faded away.
+    pub fn update(&mut self, dt: f64) -> bool {
+        self.time += dt;
+        self.center.1 -= POPUP_RISE * dt;
+        self.time < POPUP_DURATION
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        let (w, h) = self.label.size();
+        phi.queue.sprite_ex(Layer::Effects, 3, &self.label,
+            Rectangle::with_size(w, h).center_at(self.center),
+            DrawParams::new().with_opacity(1.0 - self.time / POPUP_DURATION));
+    }
+}
@@ -1,36 +1,48 @@
-use ::sdl2::render::Renderer;
+use ::phi::Phi;
 use ::phi::data::Rectangle;
-use ::phi::gfx::{CopySprite, Sprite};
+use ::phi::gfx::{Layer, Sprite};
+
+// Sounds shared by the menus
+pub const MENU_MOVE_SOUND: &'static str = "assets/sounds/menu_move.wav";
+pub const MENU_SELECT_SOUND: &'static str = "assets/sounds/menu_select.wav";
+pub const MENU_MUSIC: &'static str = "assets/sounds/music_menu.wav";
 
 #[derive(Clone)]
 pub struct Background {
 	pub pos: f64,
 	pub vel: f64,
+	// How much the background follows the camera, lower values appear further away
+	pub parallax: f64,
 	pub sprite:Sprite,
 }
 
 impl Background {
-	pub fn render(&mut self, renderer: &mut Renderer, elapsed: f64) {
+	pub fn render(&mut self, phi: &mut Phi, layer: Layer, z: i32, elapsed: f64) {
 		let size = self.sprite.size();
 		self.pos += self.vel * elapsed;
 		if self.pos > size.0 {
 			self.pos -= size.0;
 		}
 
-		let (win_w, win_h) = renderer.output_size().unwrap();
-		let scale = win_h as f64 / size.1;
+		let (win_w, win_h) = phi.output_size();
+		let scale = win_h / size.1;
+
+		// Start one tile early so that screen shake never reveals the left edge
+		let mut physical_left = -self.pos * scale - size.0 * scale;
 
-		let mut physical_left = -self.pos * scale;
+		phi.queue.set_parallax(self.parallax);
 
-		while physical_left < win_w as f64 {
-			renderer.copy_sprite(&self.sprite, Rectangle {
+		while physical_left < win_w {
+			phi.queue.sprite(layer, z, &self.sprite, Rectangle {
 				x: physical_left,
 				y: 0.0,
 				w: size.0 * scale,
-				h: win_h as f64,
+				h: win_h,
 			});
 
 			physical_left += size.0 * scale
 		}
+
+		phi.queue.set_parallax(1.0);
 	}
-}
\ No newline at end of file
+}
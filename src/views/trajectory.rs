@@ -0,0 +1,350 @@
+/// A motion described as a function of time, relative to where it started.
+/// Small trajectories combine into complex ones, e.g. this one travels right while
+/// waving up and down:
+///     Linear { vel: (240.0, 0.0) }.add(Sine { amplitude: 10.0, angular_vel: 15.0 })
+pub trait Trajectory {
+    /// Offset from the starting point after `t` seconds.
+    fn offset(&self, t: f64) -> (f64, f64);
+
+    /// Velocity after `t` seconds, in pixels per second, if it is known.
+    fn velocity(&self, _t: f64) -> Option<(f64, f64)> {
+        None
+    }
+
+    /// Follow both trajectories at once, summing their offsets.
+    fn add<U: Trajectory>(self, other: U) -> Sum<Self, U> where Self: Sized {
+        Sum(self, other)
+    }
+
+    /// Stretch the trajectory horizontally by `sx` and vertically by `sy`.
+    /// A negative factor mirrors it.
+    fn scale(self, sx: f64, sy: f64) -> Scaled<Self> where Self: Sized {
+        Scaled { inner: self, sx: sx, sy: sy }
+    }
+
+    /// Rotate the trajectory around its starting point by `angle` radians, clockwise on screen.
+    fn rotate(self, angle: f64) -> Rotated<Self> where Self: Sized {
+        Rotated { inner: self, cos: angle.cos(), sin: angle.sin() }
+    }
+
+    /// Stay put for `delay` seconds, then start following the trajectory.
+    fn delay(self, delay: f64) -> Delayed<Self> where Self: Sized {
+        Delayed { inner: self, delay: delay }
+    }
+}
+
+impl Trajectory for Box<Trajectory> {
+    fn offset(&self, t: f64) -> (f64, f64) {
+        (**self).offset(t)
+    }
+
+    fn velocity(&self, t: f64) -> Option<(f64, f64)> {
+        (**self).velocity(t)
+    }
+}
+
+
+/// Moves in a straight line at a constant velocity.
+#[derive(Clone, Copy, Debug)]
+pub struct Linear {
+    pub vel: (f64, f64),
+}
+
+impl Trajectory for Linear {
+    fn offset(&self, t: f64) -> (f64, f64) {
+        (self.vel.0 * t, self.vel.1 * t)
+    }
+
+    fn velocity(&self, _t: f64) -> Option<(f64, f64)> {
+        Some(self.vel)
+    }
+}
+
+
+/// Waves up and down around the starting point.
+#[derive(Clone, Copy, Debug)]
+pub struct Sine {
+    pub amplitude: f64,
+    /// In radians per second.
+    pub angular_vel: f64,
+}
+
+impl Trajectory for Sine {
+    fn offset(&self, t: f64) -> (f64, f64) {
+        (0.0, self.amplitude * (self.angular_vel * t).sin())
+    }
+
+    fn velocity(&self, t: f64) -> Option<(f64, f64)> {
+        Some((0.0, self.amplitude * self.angular_vel * (self.angular_vel * t).cos()))
+    }
+}
+
+
+/// Follows a vertical bump given by:
+///     a * ((t / b)^3 - (t / b)^2)
+#[derive(Clone, Copy, Debug)]
+pub struct Divergent {
+    pub a: f64, // Influences the bump's height
+    pub b: f64, // Influences the bump's width
+}
+
+impl Trajectory for Divergent {
+    fn offset(&self, t: f64) -> (f64, f64) {
+        let u = t / self.b;
+        (0.0, self.a * (u.powi(3) - u.powi(2)))
+    }
+
+    fn velocity(&self, t: f64) -> Option<(f64, f64)> {
+        let u = t / self.b;
+        Some((0.0, self.a * (3.0 * u.powi(2) - 2.0 * u) / self.b))
+    }
+}
+
+
+/// Circles around the starting point while drifting away from it.
+#[derive(Clone, Copy, Debug)]
+pub struct Spiral {
+    /// Pixels the radius grows by every second.
+    pub growth: f64,
+    /// In radians per second.
+    pub angular_vel: f64,
+}
+
+impl Trajectory for Spiral {
+    fn offset(&self, t: f64) -> (f64, f64) {
+        let radius = self.growth * t;
+        let angle = self.angular_vel * t;
+        (radius * angle.cos(), radius * angle.sin())
+    }
+
+    fn velocity(&self, t: f64) -> Option<(f64, f64)> {
+        let radius = self.growth * t;
+        let angle = self.angular_vel * t;
+        Some((self.growth * angle.cos() - radius * self.angular_vel * angle.sin(),
+              self.growth * angle.sin() + radius * self.angular_vel * angle.cos()))
+    }
+}
+
+
+/// Goes up and down in straight lines, reaching `amplitude` every `period / 2` seconds.
+#[derive(Clone, Copy, Debug)]
+pub struct ZigZag {
+    pub amplitude: f64,
+    pub period: f64,
+}
+
+impl Trajectory for ZigZag {
+    fn offset(&self, t: f64) -> (f64, f64) {
+        // Triangle wave starting at 0 and heading the same way as a sine would.
+        let phase = (t / self.period + 0.25) % 1.0;
+        let wave = if phase < 0.5 { 4.0 * phase - 1.0 } else { 3.0 - 4.0 * phase };
+        (0.0, self.amplitude * wave)
+    }
+
+    fn velocity(&self, t: f64) -> Option<(f64, f64)> {
+        let phase = (t / self.period + 0.25) % 1.0;
+        let speed = 4.0 * self.amplitude / self.period;
+        Some((0.0, if phase < 0.5 { speed } else { -speed }))
+    }
+}
+
+
+/// See `Trajectory::add`.
+#[derive(Clone, Copy, Debug)]
+pub struct Sum<A, B>(A, B);
+
+impl<A: Trajectory, B: Trajectory> Trajectory for Sum<A, B> {
+    fn offset(&self, t: f64) -> (f64, f64) {
+        let (ax, ay) = self.0.offset(t);
+        let (bx, by) = self.1.offset(t);
+        (ax + bx, ay + by)
+    }
+
+    fn velocity(&self, t: f64) -> Option<(f64, f64)> {
+        match (self.0.velocity(t), self.1.velocity(t)) {
+            (Some((ax, ay)), Some((bx, by))) => Some((ax + bx, ay + by)),
+            _ => None,
+        }
+    }
+}
+
+
+/// See `Trajectory::scale`.
+#[derive(Clone, Copy, Debug)]
+pub struct Scaled<T> {
+    inner: T,
+    sx: f64,
+    sy: f64,
+}
+
+impl<T: Trajectory> Trajectory for Scaled<T> {
+    fn offset(&self, t: f64) -> (f64, f64) {
+        let (x, y) = self.inner.offset(t);
+        (x * self.sx, y * self.sy)
+    }
+
+    fn velocity(&self, t: f64) -> Option<(f64, f64)> {
+        self.inner.velocity(t).map(|(x, y)| (x * self.sx, y * self.sy))
+    }
+}
+
+
+/// See `Trajectory::rotate`.
+#[derive(Clone, Copy, Debug)]
+pub struct Rotated<T> {
+    inner: T,
+    cos: f64,
+    sin: f64,
+}
+
+impl<T> Rotated<T> {
+    fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (x * self.cos - y * self.sin, x * self.sin + y * self.cos)
+    }
+}
+
+impl<T: Trajectory> Trajectory for Rotated<T> {
+    fn offset(&self, t: f64) -> (f64, f64) {
+        self.apply(self.inner.offset(t))
+    }
+
+    fn velocity(&self, t: f64) -> Option<(f64, f64)> {
+        self.inner.velocity(t).map(|vel| self.apply(vel))
+    }
+}
+
+
+/// See `Trajectory::delay`.
+#[derive(Clone, Copy, Debug)]
+pub struct Delayed<T> {
+    inner: T,
+    delay: f64,
+}
+
+impl<T: Trajectory> Trajectory for Delayed<T> {
+    fn offset(&self, t: f64) -> (f64, f64) {
+        self.inner.offset((t - self.delay).max(0.0))
+    }
+
+    fn velocity(&self, t: f64) -> Option<(f64, f64)> {
+        if t < self.delay {
+            Some((0.0, 0.0))
+        } else {
+            self.inner.velocity(t - self.delay)
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn assert_near(actual: (f64, f64), expected: (f64, f64)) {
+        assert!((actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+                "{:?} is not {:?}", actual, expected);
+    }
+
+    fn assert_at<T: Trajectory>(path: &T, t: f64, offset: (f64, f64), velocity: (f64, f64)) {
+        assert_near(path.offset(t), offset);
+        assert_near(path.velocity(t).expect("velocity should be known"), velocity);
+    }
+
+    /// Only knows where it is, not how fast it goes.
+    struct Still;
+
+    impl Trajectory for Still {
+        fn offset(&self, _t: f64) -> (f64, f64) {
+            (1.0, 1.0)
+        }
+    }
+
+    #[test]
+    fn linear() {
+        let path = Linear { vel: (240.0, -60.0) };
+        assert_at(&path, 0.0, (0.0, 0.0), (240.0, -60.0));
+        assert_at(&path, 0.5, (120.0, -30.0), (240.0, -60.0));
+    }
+
+    #[test]
+    fn sine() {
+        let path = Sine { amplitude: 10.0, angular_vel: PI };
+        assert_at(&path, 0.0, (0.0, 0.0), (0.0, 10.0 * PI));
+        assert_at(&path, 0.5, (0.0, 10.0), (0.0, 0.0));
+        assert_at(&path, 1.5, (0.0, -10.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn zigzag() {
+        let path = ZigZag { amplitude: 10.0, period: 2.0 };
+        assert_at(&path, 0.0, (0.0, 0.0), (0.0, 20.0));
+        assert_at(&path, 0.25, (0.0, 5.0), (0.0, 20.0));
+        assert_at(&path, 0.75, (0.0, 5.0), (0.0, -20.0));
+        assert_at(&path, 1.0, (0.0, 0.0), (0.0, -20.0));
+        assert_at(&path, 2.25, (0.0, 5.0), (0.0, 20.0));
+        assert_near(path.offset(0.5), (0.0, 10.0));
+        assert_near(path.offset(1.5), (0.0, -10.0));
+    }
+
+    #[test]
+    fn divergent() {
+        let path = Divergent { a: 8.0, b: 2.0 };
+        assert_at(&path, 0.0, (0.0, 0.0), (0.0, 0.0));
+        assert_at(&path, 1.0, (0.0, -1.0), (0.0, -1.0));
+        assert_at(&path, 2.0, (0.0, 0.0), (0.0, 4.0));
+    }
+
+    #[test]
+    fn spiral() {
+        let path = Spiral { growth: 10.0, angular_vel: PI / 2.0 };
+        assert_at(&path, 0.0, (0.0, 0.0), (10.0, 0.0));
+        assert_at(&path, 1.0, (0.0, 10.0), (-5.0 * PI, 10.0));
+        assert_at(&path, 2.0, (-20.0, 0.0), (-10.0, -10.0 * PI));
+    }
+
+    #[test]
+    fn add_sums_offsets_and_velocities() {
+        let path = Linear { vel: (100.0, 0.0) }.add(Sine { amplitude: 10.0, angular_vel: PI });
+        assert_at(&path, 0.0, (0.0, 0.0), (100.0, 10.0 * PI));
+        assert_at(&path, 0.5, (50.0, 10.0), (100.0, 0.0));
+
+        // The sum's velocity is only known if both parts' are
+        assert_eq!(Linear { vel: (1.0, 0.0) }.add(Still).velocity(1.0), None);
+        assert_near(Linear { vel: (1.0, 0.0) }.add(Still).offset(1.0), (2.0, 1.0));
+    }
+
+    #[test]
+    fn scale_stretches_and_mirrors() {
+        let path = Linear { vel: (10.0, 20.0) }.scale(2.0, -1.0);
+        assert_at(&path, 1.0, (20.0, -20.0), (20.0, -20.0));
+
+        let mirrored = Sine { amplitude: 10.0, angular_vel: PI }.scale(1.0, -1.0);
+        assert_at(&mirrored, 0.5, (0.0, -10.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn rotate_turns_clockwise_on_screen() {
+        let down = Linear { vel: (10.0, 0.0) }.rotate(PI / 2.0);
+        assert_at(&down, 1.0, (0.0, 10.0), (0.0, 10.0));
+
+        let back = Linear { vel: (10.0, 5.0) }.rotate(PI);
+        assert_at(&back, 2.0, (-20.0, -10.0), (-10.0, -5.0));
+
+        let waving_left = Linear { vel: (100.0, 0.0) }
+            .add(Sine { amplitude: 10.0, angular_vel: PI }.rotate(PI));
+        assert_at(&waving_left, 0.5, (50.0, -10.0), (100.0, 0.0));
+    }
+
+    #[test]
+    fn delay_holds_still_then_starts_over() {
+        let path = Linear { vel: (10.0, 0.0) }.delay(1.0);
+        assert_at(&path, 0.0, (0.0, 0.0), (0.0, 0.0));
+        assert_at(&path, 0.5, (0.0, 0.0), (0.0, 0.0));
+        assert_at(&path, 3.0, (20.0, 0.0), (10.0, 0.0));
+
+        let wave = Sine { amplitude: 10.0, angular_vel: PI }.delay(0.5);
+        assert_at(&wave, 1.0, (0.0, 10.0), (0.0, 0.0));
+    }
+}